@@ -0,0 +1,228 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::number::*;
+use crate::vector::*;
+use crate::functional::*;
+use crate::stats::*;
+use crate::fit::*;
+use crate::random::*;
+use crate::probability::*;
+use crate::elementary::*;
+use crate::symbolic::*;
+use crate::complex::*;
+use crate::polynomial::*;
+use crate::quadrature::*;
+use crate::ode::*;
+use crate::optimize::*;
+use crate::dual::*;
+use crate::signal::*;
+
+pub type Builtin = fn(&[(Value, Position)], &Position, &str, &mut Context) -> Result<Value, Error>;
+
+pub fn builtin(name: &str) -> Option<Builtin> {
+    match name {
+        "gcd" => Some(gcd),
+        "lcm" => Some(lcm),
+        "isprime" => Some(isprime),
+        "factor" => Some(factor),
+        "nextprime" => Some(nextprime),
+        "modpow" => Some(modpow),
+        "modinv" => Some(modinv),
+        "totient" => Some(totient),
+        "divisors" => Some(divisors),
+        "binom" => Some(binom),
+        "perm" => Some(perm),
+        "linspace" => Some(linspace),
+        "logspace" => Some(logspace),
+        "len" => Some(len),
+        "push" => Some(push),
+        "reverse" => Some(reverse),
+        "sort" => Some(sort),
+        "unique" => Some(unique),
+        "find" => Some(find),
+        "take" => Some(take),
+        "drop" => Some(drop),
+        "zip" => Some(zip),
+        "flatten" => Some(flatten),
+        "repeat" => Some(repeat),
+        "any" => Some(any),
+        "all" => Some(all),
+        "equal" => Some(equal),
+        "map" => Some(map),
+        "filter" => Some(filter),
+        "reduce" => Some(reduce),
+        "fold" => Some(fold),
+        "scan" => Some(scan),
+        "count" => Some(count),
+        "iterate" => Some(iterate),
+        "sum" => Some(sum),
+        "prod" => Some(prod),
+        "mean" => Some(mean),
+        "median" => Some(median),
+        "mode" => Some(mode),
+        "var" => Some(var),
+        "varp" => Some(varp),
+        "std" => Some(std),
+        "stdp" => Some(stdp),
+        "quantile" => Some(quantile),
+        "min" => Some(min),
+        "max" => Some(max),
+        "argmin" => Some(argmin),
+        "argmax" => Some(argmax),
+        "cov" => Some(cov),
+        "corr" => Some(corr),
+        "histogram" => Some(histogram),
+        "linfit" => Some(linfit),
+        "polyfit" => Some(polyfit),
+        "fit" => Some(fit),
+        "model" => Some(model),
+        "seed" => Some(seed),
+        "rand" => Some(rand),
+        "randn" => Some(randn),
+        "randint" => Some(randint),
+        "sample" => Some(sample),
+        "normpdf" => Some(normpdf),
+        "normcdf" => Some(normcdf),
+        "norminv" => Some(norminv),
+        "unifpdf" => Some(unifpdf),
+        "unifcdf" => Some(unifcdf),
+        "unifinv" => Some(unifinv),
+        "binompdf" => Some(binompdf),
+        "binomcdf" => Some(binomcdf),
+        "binominv" => Some(binominv),
+        "poisspdf" => Some(poisspdf),
+        "poisscdf" => Some(poisscdf),
+        "poissinv" => Some(poissinv),
+        "exppdf" => Some(exppdf),
+        "expcdf" => Some(expcdf),
+        "expinv" => Some(expinv),
+        "tpdf" => Some(tpdf),
+        "tcdf" => Some(tcdf),
+        "tinv" => Some(tinv),
+        "sin" => Some(sin),
+        "cos" => Some(cos),
+        "tan" => Some(tan),
+        "asin" => Some(asin),
+        "acos" => Some(acos),
+        "atan" => Some(atan),
+        "sinh" => Some(sinh),
+        "cosh" => Some(cosh),
+        "tanh" => Some(tanh),
+        "exp" => Some(exp),
+        "ln" => Some(ln),
+        "log" => Some(log),
+        "sqrt" => Some(sqrt),
+        "abs" => Some(abs),
+        "d" => Some(d),
+        "diff" => Some(diff),
+        "simplify" => Some(simplify),
+        "symbolic" => Some(symbolic),
+        "complex" => Some(complex),
+        "re" => Some(re),
+        "im" => Some(im),
+        "conj" => Some(conj),
+        "arg" => Some(arg),
+        "poly" => Some(poly),
+        "coeffs" => Some(coeffs),
+        "deg" => Some(deg),
+        "pdiv" => Some(pdiv),
+        "roots" => Some(roots),
+        "integrate" => Some(integrate),
+        "int" => Some(int),
+        "taylor" => Some(taylor),
+        "ode" => Some(ode),
+        "minimize" => Some(minimize),
+        "maximize" => Some(maximize),
+        "golden" => Some(golden),
+        "grad" => Some(grad),
+        "jacobian" => Some(jacobian),
+        "hessian" => Some(hessian),
+        "fft" => Some(fft),
+        "ifft" => Some(ifft),
+        "conv" => Some(conv),
+        "xcorr" => Some(xcorr),
+        "hann" => Some(hann),
+        "hamming" => Some(hamming),
+        "blackman" => Some(blackman),
+        _ => None
+    }
+}
+
+// -- ARGUMENTS ------------------------------------------------------------------------
+
+pub fn expect_args(name: &str, args: &[(Value, Position)], count: usize, pos: &Position, file_path: &str) -> Result<(), Error> {
+    if args.len() != count {
+        return Err(Error::ArgumentCount(name.to_string(), count, args.len(), pos.clone(), file_path.to_string()))
+    }
+    Ok(())
+}
+pub fn illegal_arg(name: &str, arg: &(Value, Position), file_path: &str) -> Error {
+    Error::IllegalValue(arg.0.clone(), Type::Builtin(name.to_string()), arg.1.clone(), file_path.to_string())
+}
+pub fn expect_int(name: &str, arg: &(Value, Position), file_path: &str) -> Result<i64, Error> {
+    match &arg.0 {
+        Value::Int(int) => Ok(*int),
+        _ => Err(illegal_arg(name, arg, file_path))
+    }
+}
+pub fn expect_vector<'a>(name: &str, arg: &'a (Value, Position), file_path: &str) -> Result<&'a Vec<Value>, Error> {
+    match &arg.0 {
+        Value::Vector(vector) => Ok(vector),
+        _ => Err(illegal_arg(name, arg, file_path))
+    }
+}
+pub fn expect_float(name: &str, arg: &(Value, Position), file_path: &str) -> Result<f64, Error> {
+    match &arg.0 {
+        Value::Int(int) => Ok(*int as f64),
+        Value::Float(float) => Ok(*float),
+        _ => Err(illegal_arg(name, arg, file_path))
+    }
+}
+pub fn expect_callable(name: &str, arg: &(Value, Position), file_path: &str) -> Result<Value, Error> {
+    match &arg.0 {
+        Value::Function(_, _) | Value::Builtin(_) | Value::Poly(_) => Ok(arg.0.clone()),
+        Value::Expr(node) => match free_vars(node).as_slice() {
+            [var] => Ok(Value::Function(vec![var.clone()], node.clone())),
+            vars => Err(Error::Domain(
+                format!("{name} expects an expression in one variable, got {}", vars.join(" ")), arg.1.clone(), file_path.to_string()
+            ))
+        }
+        _ => Err(illegal_arg(name, arg, file_path))
+    }
+}
+pub fn expect_point(name: &str, arg: &(Value, Position), file_path: &str) -> Result<Vec<f64>, Error> {
+    let point = match &arg.0 {
+        Value::Vector(values) => values.iter().map(|v| v.number()).collect::<Option<Vec<f64>>>(),
+        value => value.number().map(|x| vec![x])
+    }.ok_or_else(|| illegal_arg(name, arg, file_path))?;
+    if point.is_empty() { return Err(Error::Empty(name.to_string(), arg.1.clone(), file_path.to_string())) }
+    Ok(point)
+}
+pub fn map_int(name: &str, arg: &(Value, Position), file_path: &str,
+    func: &dyn Fn(i64) -> Result<Value, Error>) -> Result<Value, Error> {
+    match &arg.0 {
+        Value::Int(int) => func(*int),
+        Value::Vector(values) => {
+            let mut vector: Vec<Value> = vec![];
+            for value in values {
+                vector.push(map_int(name, &(value.clone(), arg.1.clone()), file_path, func)?);
+            }
+            Ok(Value::Vector(vector))
+        }
+        _ => Err(illegal_arg(name, arg, file_path))
+    }
+}
+pub fn map_float(name: &str, arg: &(Value, Position), file_path: &str,
+    func: &dyn Fn(f64) -> Result<Value, Error>) -> Result<Value, Error> {
+    match &arg.0 {
+        Value::Vector(values) => {
+            let mut vector: Vec<Value> = vec![];
+            for value in values {
+                vector.push(map_float(name, &(value.clone(), arg.1.clone()), file_path, func)?);
+            }
+            Ok(Value::Vector(vector))
+        }
+        value => func(value.number().ok_or_else(|| illegal_arg(name, arg, file_path))?)
+    }
+}
@@ -0,0 +1,144 @@
+use std::fmt::{Display, Formatter, Error as FMTError};
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64
+}
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self { Self { re, im } }
+    pub fn norm(&self) -> f64 { self.re.hypot(self.im) }
+    pub fn arg(&self) -> f64 { self.im.atan2(self.re) }
+    pub fn conj(&self) -> Self { Self::new(self.re, -self.im) }
+    pub fn polar(r: f64, theta: f64) -> Self { Self::new(r * theta.cos(), r * theta.sin()) }
+    pub fn powf(&self, exp: f64) -> Self {
+        if *self == Self::new(0.0, 0.0) { return if exp == 0.0 { Self::new(1.0, 0.0) } else { *self } }
+        Self::polar(self.norm().powf(exp), self.arg() * exp)
+    }
+    pub fn powi(&self, exp: i64) -> Self {
+        let (mut result, mut base, mut n) = (Self::new(1.0, 0.0), *self, exp.unsigned_abs());
+        while n > 0 {
+            if n & 1 == 1 { result = result * base }
+            base = base * base;
+            n >>= 1;
+        }
+        if exp < 0 { Self::new(1.0, 0.0) / result } else { result }
+    }
+}
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, other: Self) -> Self { Self::new(self.re + other.re, self.im + other.im) }
+}
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self { Self::new(self.re - other.re, self.im - other.im) }
+}
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(self.re * other.re - self.im * other.im, self.re * other.im + self.im * other.re)
+    }
+}
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        let den = other.re * other.re + other.im * other.im;
+        Self::new((self.re * other.re + self.im * other.im) / den, (self.im * other.re - self.re * other.im) / den)
+    }
+}
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self { Self::new(-self.re, -self.im) }
+}
+impl Display for Complex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FMTError> {
+        if self.im.is_sign_negative() { write!(f, "{} - {}i", self.re, -self.im) } else { write!(f, "{} + {}i", self.re, self.im) }
+    }
+}
+
+// -- ARITHMETIC -----------------------------------------------------------------------
+
+pub fn as_complex(value: &Value) -> Option<Complex> {
+    match value {
+        Value::Complex(z) => Some(*z),
+        value => Some(Complex::new(value.number()?, 0.0))
+    }
+}
+pub fn complex_binary(op: &Token, left: &Value, right: &Value) -> Option<Value> {
+    let (l, r) = (as_complex(left)?, as_complex(right)?);
+    match op {
+        Token::Add => Some(Value::Complex(l + r)),
+        Token::Subtract => Some(Value::Complex(l - r)),
+        Token::Multiply => Some(Value::Complex(l * r)),
+        Token::Divide => Some(Value::Complex(l / r)),
+        Token::Power => match right {
+            Value::Int(exp) => Some(Value::Complex(l.powi(*exp))),
+            Value::Float(exp) => Some(Value::Complex(l.powf(*exp))),
+            _ => None
+        }
+        Token::Equal => Some(Value::Bool(l == r)),
+        Token::NotEqual => Some(Value::Bool(l != r)),
+        _ => None
+    }
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn map_complex(name: &str, arg: &(Value, Position), file_path: &str, func: &dyn Fn(Complex) -> Value) -> Result<Value, Error> {
+    match &arg.0 {
+        Value::Vector(values) => Ok(Value::Vector(values.iter()
+            .map(|value| map_complex(name, &(value.clone(), arg.1.clone()), file_path, func))
+            .collect::<Result<Vec<Value>, Error>>()?)),
+        value => Ok(func(as_complex(value).ok_or_else(|| illegal_arg(name, arg, file_path))?))
+    }
+}
+pub fn complex(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("complex", args, 2, pos, file_path)?;
+    let re = expect_float("complex", &args[0], file_path)?;
+    let im = expect_float("complex", &args[1], file_path)?;
+    Ok(Value::Complex(Complex::new(re, im)))
+}
+pub fn re(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("re", args, 1, pos, file_path)?;
+    map_complex("re", &args[0], file_path, &|z| Value::Float(z.re))
+}
+pub fn im(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("im", args, 1, pos, file_path)?;
+    map_complex("im", &args[0], file_path, &|z| Value::Float(z.im))
+}
+pub fn conj(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("conj", args, 1, pos, file_path)?;
+    map_complex("conj", &args[0], file_path, &|z| Value::Complex(z.conj()))
+}
+pub fn arg(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("arg", args, 1, pos, file_path)?;
+    map_complex("arg", &args[0], file_path, &|z| Value::Float(z.arg()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_powers_square_repeatedly() {
+        let i = Complex::new(0.0, 1.0);
+        assert!(i.powi(2) == Complex::new(-1.0, 0.0));
+        assert!(Complex::new(1.0, 1.0).powi(8) == Complex::new(16.0, 0.0));
+        assert!(Complex::new(2.0, 0.0).powi(-2) == Complex::new(0.25, 0.0));
+        assert!(Complex::new(1.0, 0.0).powi(10_000_000_000) == Complex::new(1.0, 0.0));
+        let z = Complex::new(0.6, 0.8).powi(1001);
+        assert!((z - Complex::new(0.6, 0.8).powf(1001.0)).norm() < 1e-9);
+    }
+    #[test]
+    fn complex_values_in_the_language() {
+        assert_eq!(eval_lines(&["complex(1 2) * complex(3 (-1))"]).unwrap().to_string(), "5 + 5i");
+        assert_eq!(eval_lines(&["abs(complex(3 4))"]).unwrap().to_string(), "5");
+        assert_eq!(eval_lines(&["complex(1 0)^10000000000"]).unwrap().to_string(), "1 + 0i");
+    }
+}
\ No newline at end of file
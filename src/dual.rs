@@ -0,0 +1,232 @@
+use std::fmt::{Display, Formatter, Error as FMTError};
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+
+// a truncated second order Taylor expansion, the hessian is left empty when only gradients are needed
+#[derive(Clone, PartialEq)]
+pub struct Dual {
+    pub re: f64,
+    pub grad: Vec<f64>,
+    pub hess: Vec<f64>
+}
+impl Dual {
+    pub fn constant(re: f64, like: &Dual) -> Self {
+        Self { re, grad: vec![0.0; like.grad.len()], hess: vec![0.0; like.hess.len()] }
+    }
+    pub fn variable(re: f64, i: usize, n: usize, second: bool) -> Self {
+        let mut grad = vec![0.0; n];
+        grad[i] = 1.0;
+        Self { re, grad, hess: if second { vec![0.0; n * n] } else { vec![] } }
+    }
+    fn chain(&self, f0: f64, f1: f64, f2: f64) -> Self {
+        let n = self.grad.len();
+        Self {
+            re: f0,
+            grad: self.grad.iter().map(|g| f1 * g).collect(),
+            hess: self.hess.iter().enumerate().map(|(k, h)| f1 * h + f2 * self.grad[k / n] * self.grad[k % n]).collect()
+        }
+    }
+    pub fn apply(&self, name: &str) -> Option<Self> {
+        let x = self.re;
+        let (f0, f1, f2) = match name {
+            "sin" => (x.sin(), x.cos(), -x.sin()),
+            "cos" => (x.cos(), -x.sin(), -x.cos()),
+            "tan" => { let t = x.tan(); (t, 1.0 + t * t, 2.0 * t * (1.0 + t * t)) }
+            "asin" => (x.asin(), 1.0 / (1.0 - x * x).sqrt(), x / (1.0 - x * x).powf(1.5)),
+            "acos" => (x.acos(), -1.0 / (1.0 - x * x).sqrt(), -x / (1.0 - x * x).powf(1.5)),
+            "atan" => (x.atan(), 1.0 / (1.0 + x * x), -2.0 * x / (1.0 + x * x).powi(2)),
+            "sinh" => (x.sinh(), x.cosh(), x.sinh()),
+            "cosh" => (x.cosh(), x.sinh(), x.cosh()),
+            "tanh" => { let t = x.tanh(); (t, 1.0 - t * t, -2.0 * t * (1.0 - t * t)) }
+            "exp" => (x.exp(), x.exp(), x.exp()),
+            "ln" => (x.ln(), 1.0 / x, -1.0 / (x * x)),
+            "log" => (x.log10(), 1.0 / (x * 10f64.ln()), -1.0 / (x * x * 10f64.ln())),
+            "sqrt" => (x.sqrt(), 0.5 / x.sqrt(), -0.25 / (x * x.sqrt())),
+            "abs" => (x.abs(), if x == 0.0 { 0.0 } else { x.signum() }, 0.0),
+            _ => return None
+        };
+        Some(self.chain(f0, f1, f2))
+    }
+    pub fn powf(&self, exp: f64) -> Self {
+        if exp == 0.0 { return Self::constant(1.0, self) }
+        if exp == 1.0 { return self.clone() }
+        let x = self.re;
+        self.chain(x.powf(exp), exp * x.powf(exp - 1.0), exp * (exp - 1.0) * x.powf(exp - 2.0))
+    }
+    fn times(&self, other: &Self) -> Self {
+        let n = self.grad.len();
+        Self {
+            re: self.re * other.re,
+            grad: self.grad.iter().zip(&other.grad).map(|(a, b)| other.re * a + self.re * b).collect(),
+            hess: self.hess.iter().zip(&other.hess).enumerate().map(|(k, (a, b))| {
+                let (i, j) = (k / n, k % n);
+                other.re * a + self.re * b + self.grad[i] * other.grad[j] + other.grad[i] * self.grad[j]
+            }).collect()
+        }
+    }
+    fn recip(&self) -> Self {
+        let x = self.re;
+        self.chain(1.0 / x, -1.0 / (x * x), 2.0 / (x * x * x))
+    }
+}
+impl Add for Dual {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self {
+            re: self.re + other.re,
+            grad: self.grad.iter().zip(&other.grad).map(|(a, b)| a + b).collect(),
+            hess: self.hess.iter().zip(&other.hess).map(|(a, b)| a + b).collect()
+        }
+    }
+}
+impl Sub for Dual {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self { self + -other }
+}
+impl Mul for Dual {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self { self.times(&other) }
+}
+impl Div for Dual {
+    type Output = Self;
+    fn div(self, other: Self) -> Self { self.times(&other.recip()) }
+}
+impl Neg for Dual {
+    type Output = Self;
+    fn neg(self) -> Self { self.chain(-self.re, -1.0, 0.0) }
+}
+impl Display for Dual {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FMTError> {
+        write!(f, "{} + [{}]ε", self.re, self.grad.iter().map(|g| format!("{g}")).collect::<Vec<String>>().join(" "))
+    }
+}
+
+// -- ARITHMETIC -----------------------------------------------------------------------
+
+pub fn real(value: &Value) -> Option<f64> {
+    match value {
+        Value::Dual(x) => Some(x.re),
+        value => value.number()
+    }
+}
+// builtins that only work on plain numbers name themselves when a dual reaches them
+pub fn reject_duals(name: &str, args: &[(Value, Position)], file_path: &str) -> Result<(), Error> {
+    fn holds_dual(value: &Value) -> bool {
+        match value {
+            Value::Dual(_) => true,
+            Value::Vector(values) => values.iter().any(holds_dual),
+            _ => false
+        }
+    }
+    match args.iter().find(|arg| holds_dual(&arg.0)) {
+        Some(arg) => Err(Error::Domain(format!("{name} does not support automatic differentiation"), arg.1.clone(), file_path.to_string())),
+        None => Ok(())
+    }
+}
+pub fn dual_binary(op: &Token, left: &Value, right: &Value) -> Option<Value> {
+    let like = match (left, right) {
+        (Value::Dual(x), _) | (_, Value::Dual(x)) => x,
+        _ => return None
+    };
+    let promote = |value: &Value| match value {
+        Value::Dual(x) => Some(x.clone()),
+        value => Some(Dual::constant(value.number()?, like))
+    };
+    let (l, r) = (promote(left)?, promote(right)?);
+    match op {
+        Token::Add => Some(Value::Dual(l + r)),
+        Token::Subtract => Some(Value::Dual(l - r)),
+        Token::Multiply => Some(Value::Dual(l * r)),
+        Token::Divide => Some(Value::Dual(l / r)),
+        Token::Power => match right {
+            Value::Dual(_) => Some(Value::Dual((r * l.apply("ln")?).apply("exp")?)),
+            value => Some(Value::Dual(l.powf(value.number()?)))
+        }
+        Token::Equal => Some(Value::Bool(l.re == r.re)),
+        Token::NotEqual => Some(Value::Bool(l.re != r.re)),
+        Token::Less => Some(Value::Bool(l.re < r.re)),
+        Token::Greater => Some(Value::Bool(l.re > r.re)),
+        Token::LessEqual => Some(Value::Bool(l.re <= r.re)),
+        Token::GreaterEqual => Some(Value::Bool(l.re >= r.re)),
+        _ => None
+    }
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn evaluate(name: &str, args: &[(Value, Position)], second: bool, pos: &Position, file_path: &str, context: &mut Context)
+-> Result<(Value, bool, usize), Error> {
+    expect_args(name, args, 2, pos, file_path)?;
+    let func = expect_callable(name, &args[0], file_path)?;
+    let scalar = !matches!(args[1].0, Value::Vector(_));
+    let x = expect_point(name, &args[1], file_path)?;
+    let n = x.len();
+    let duals: Vec<Value> = x.iter().enumerate().map(|(i, x)| Value::Dual(Dual::variable(*x, i, n, second))).collect();
+    let spread = matches!(&func, Value::Function(params, _) if params.len() == n && n > 1);
+    let call_args: Vec<(Value, Position)> = if spread || scalar {
+        duals.into_iter().map(|x| (x, args[1].1.clone())).collect()
+    } else {
+        vec![(Value::Vector(duals), args[1].1.clone())]
+    };
+    Ok((call(&func, None, &call_args, pos, file_path, context)?, scalar, n))
+}
+fn output(name: &str, value: &Value, n: usize, second: bool, pos: &Position, file_path: &str) -> Result<Dual, Error> {
+    match value {
+        Value::Dual(x) => Ok(x.clone()),
+        value => value.number().map(|re| Dual { re, grad: vec![0.0; n], hess: vec![0.0; if second { n * n } else { 0 }] })
+            .ok_or_else(|| Error::IllegalValue(value.clone(), Type::Builtin(name.to_string()), pos.clone(), file_path.to_string()))
+    }
+}
+fn floats(values: &[f64]) -> Value {
+    Value::Vector(values.iter().map(|x| Value::Float(*x)).collect())
+}
+pub fn grad(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    let (value, scalar, n) = evaluate("grad", args, false, pos, file_path, context)?;
+    let y = output("grad", &value, n, false, pos, file_path)?;
+    Ok(if scalar { Value::Float(y.grad[0]) } else { floats(&y.grad) })
+}
+pub fn jacobian(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    let (value, _, n) = evaluate("jacobian", args, false, pos, file_path, context)?;
+    let outputs = match &value { Value::Vector(values) => values.clone(), value => vec![value.clone()] };
+    Ok(Value::Vector(outputs.iter().map(|y| Ok(floats(&output("jacobian", y, n, false, pos, file_path)?.grad)))
+        .collect::<Result<Vec<Value>, Error>>()?))
+}
+pub fn hessian(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    let (value, scalar, n) = evaluate("hessian", args, true, pos, file_path, context)?;
+    let y = output("hessian", &value, n, true, pos, file_path)?;
+    Ok(if scalar { Value::Float(y.hess[0]) } else { Value::Vector(y.hess.chunks(n).map(floats).collect()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn products_and_quotients_carry_second_derivatives() {
+        let x = Dual::variable(2.0, 0, 1, true);
+        let y = x.clone() * x.clone() * x.clone() / (x.clone() + Dual::constant(1.0, &x));
+        // y = x^3 / (x + 1), y' = (2x^3 + 3x^2) / (x + 1)^2, y'' = 2x (x^2 + 3x + 3) / (x + 1)^3
+        assert!((y.re - 8.0 / 3.0).abs() < 1e-12);
+        assert!((y.grad[0] - 28.0 / 9.0).abs() < 1e-12);
+        assert!((y.hess[0] - 52.0 / 27.0).abs() < 1e-12);
+    }
+    #[test]
+    fn statistics_and_sort_see_through_duals() {
+        assert_eq!(eval_lines(&["c(x) : mean([x x^2])", "grad(c 2)"]).unwrap().to_string(), "2.5");
+        assert_eq!(eval_lines(&["v(x) : var([x 1 2])", "grad(v 3)"]).unwrap().to_string(), "1");
+        assert_eq!(eval_lines(&["m(x) : median([x 1 5])", "grad(m 2)"]).unwrap().to_string(), "1");
+        assert_eq!(eval_lines(&["h(x) : sum(sort([x 1]) * [1 10])", "[grad(h 2) grad(h 0)]"]).unwrap().to_string(), "[10, 1]");
+    }
+    #[test]
+    fn number_only_builtins_name_themselves() {
+        for (body, name) in [("normcdf(x 0 1)", "normcdf"), ("integrate(sin 0 x)", "integrate")] {
+            let define = format!("f(x) : {body}");
+            let error = eval_lines(&[&define, "grad(f 1)"]).unwrap_err();
+            assert!(error.to_string().starts_with(&format!("ERROR: {name} does not support automatic differentiation")));
+        }
+    }
+}
\ No newline at end of file
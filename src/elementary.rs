@@ -0,0 +1,89 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::symbolic::*;
+use crate::dual::*;
+
+fn dual_of(name: &str, x: &Dual, pos: &Position, file_path: &str) -> Result<Value, Error> {
+    match x.apply(name) {
+        Some(y) if !y.re.is_nan() => Ok(Value::Dual(y)),
+        _ => Err(Error::Domain(format!("{name} is undefined for {}", x.re), pos.clone(), file_path.to_string()))
+    }
+}
+fn elementary(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str, func: fn(f64) -> f64)
+-> Result<Value, Error> {
+    expect_args(name, args, 1, pos, file_path)?;
+    match &args[0].0 {
+        Value::Expr(node) => return Ok(expression(simplify_node(&apply(name, node.clone(), pos), pos))),
+        Value::Dual(x) => return dual_of(name, x, &args[0].1, file_path),
+        Value::Vector(values) if values.iter().any(|value| matches!(value, Value::Dual(_))) => return Ok(Value::Vector(values.iter()
+            .map(|value| elementary(name, &[(value.clone(), args[0].1.clone())], pos, file_path, func))
+            .collect::<Result<Vec<Value>, Error>>()?)),
+        _ => {}
+    }
+    map_float(name, &args[0], file_path, &|x| {
+        let y = func(x);
+        if y.is_nan() && !x.is_nan() { return Err(Error::Domain(
+            format!("{name} is undefined for {x}"), args[0].1.clone(), file_path.to_string()
+        )) }
+        Ok(Value::Float(y))
+    })
+}
+
+pub fn sin(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("sin", args, pos, file_path, f64::sin)
+}
+pub fn cos(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("cos", args, pos, file_path, f64::cos)
+}
+pub fn tan(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("tan", args, pos, file_path, f64::tan)
+}
+pub fn asin(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("asin", args, pos, file_path, f64::asin)
+}
+pub fn acos(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("acos", args, pos, file_path, f64::acos)
+}
+pub fn atan(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("atan", args, pos, file_path, f64::atan)
+}
+pub fn sinh(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("sinh", args, pos, file_path, f64::sinh)
+}
+pub fn cosh(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("cosh", args, pos, file_path, f64::cosh)
+}
+pub fn tanh(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("tanh", args, pos, file_path, f64::tanh)
+}
+pub fn exp(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("exp", args, pos, file_path, f64::exp)
+}
+pub fn ln(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("ln", args, pos, file_path, f64::ln)
+}
+pub fn log(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("log", args, pos, file_path, f64::log10)
+}
+pub fn sqrt(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    elementary("sqrt", args, pos, file_path, f64::sqrt)
+}
+fn abs_of(arg: &(Value, Position), file_path: &str) -> Result<Value, Error> {
+    match &arg.0 {
+        Value::Complex(z) => Ok(Value::Float(z.norm())),
+        Value::Dual(x) => dual_of("abs", x, &arg.1, file_path),
+        Value::Int(int) => int.checked_abs().map(Value::Int)
+            .ok_or_else(|| Error::Overflow("abs".to_string(), arg.1.clone(), file_path.to_string())),
+        Value::Vector(values) => Ok(Value::Vector(
+            values.iter().map(|value| abs_of(&(value.clone(), arg.1.clone()), file_path)).collect::<Result<Vec<Value>, Error>>()?
+        )),
+        value => Ok(Value::Float(value.number().ok_or_else(|| illegal_arg("abs", arg, file_path))?.abs()))
+    }
+}
+pub fn abs(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("abs", args, 1, pos, file_path)?;
+    if let Value::Expr(_) = &args[0].0 { return elementary("abs", args, pos, file_path, f64::abs) }
+    abs_of(&args[0], file_path)
+}
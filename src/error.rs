@@ -11,9 +11,15 @@ pub enum Error {
     UnexpectedToken(Token, Position, String),
     BinaryOperation(Token, Value, Value, Position, String),
     UnaryOperation(Token, Value, Position, String),
-    Index(usize, usize, Position, String),
+    Index(i64, usize, Position, String),
     IllegalValue(Value, Type, Position, String),
     Variable(String, Position, String),
+    ArgumentCount(String, usize, usize, Position, String),
+    Overflow(String, Position, String),
+    Domain(String, Position, String),
+    Shape(usize, usize, Position, String),
+    Empty(String, Position, String),
+    Converge(String, Position, String),
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FMTError> {
@@ -32,12 +38,24 @@ impl Display for Error {
             Self::UnaryOperation(op, value, pos, path) =>
             write!(f, "ERROR: operation {} cannot be performed on {} - {path} {pos}",
             op.name(), value.type_()),
-            Self::Index(vector_len, index, pos, path) =>
-            write!(f, "ERROR: index {index} out of range, max {vector_len} - {path} {pos}"),
+            Self::Index(index, vector_len, pos, path) =>
+            write!(f, "ERROR: index {index} out of range for vector of length {vector_len} - {path} {pos}"),
             Self::IllegalValue(value, typ, pos, path) =>
             write!(f, "ERROR: {} illegal for {typ} - {path} {pos}", value.type_()),
             Self::Variable(var, pos, path) =>
             write!(f, "ERROR: {var} not defined - {path} {pos}"),
+            Self::ArgumentCount(func, expected, got, pos, path) =>
+            write!(f, "ERROR: {func} expected {expected} arguments got {got} - {path} {pos}"),
+            Self::Overflow(op, pos, path) =>
+            write!(f, "ERROR: integer overflow in {op} - {path} {pos}"),
+            Self::Domain(detail, pos, path) =>
+            write!(f, "ERROR: {detail} - {path} {pos}"),
+            Self::Shape(expected, got, pos, path) =>
+            write!(f, "ERROR: shape mismatch, expected {expected} elements got {got} - {path} {pos}"),
+            Self::Empty(func, pos, path) =>
+            write!(f, "ERROR: {func} of empty vector - {path} {pos}"),
+            Self::Converge(detail, pos, path) =>
+            write!(f, "ERROR: {detail} did not converge - {path} {pos}"),
         }
     }
 }
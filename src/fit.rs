@@ -0,0 +1,147 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::stats::*;
+use crate::linalg::*;
+use crate::symbolic::*;
+
+fn floats(numbers: &[f64]) -> Value {
+    Value::Vector(numbers.iter().map(|x| Value::Float(*x + 0.0)).collect())
+}
+fn fit_result(params: &[f64], y: &[f64], predicted: &[f64]) -> Value {
+    let residuals: Vec<f64> = y.iter().zip(predicted.iter()).map(|(y, p)| y - p).collect();
+    let mean = mean_of(y);
+    let ss_res: f64 = residuals.iter().map(|r| r * r).sum();
+    let ss_tot: f64 = y.iter().map(|y| (y - mean).powi(2)).sum();
+    let r2 = if ss_tot == 0.0 { if ss_res == 0.0 { 1.0 } else { 0.0 } } else { 1.0 - ss_res / ss_tot };
+    Value::Vector(vec![floats(params), Value::Float(r2), floats(&residuals)])
+}
+fn expect_xy(name: &str, args: &[(Value, Position)], min: usize, file_path: &str) -> Result<(Vec<f64>, Vec<f64>), Error> {
+    let x = expect_numbers(name, &args[0], file_path)?;
+    let y = expect_numbers(name, &args[1], file_path)?;
+    if x.len() != y.len() { return Err(Error::Shape(x.len(), y.len(), args[1].1.clone(), file_path.to_string())) }
+    if x.is_empty() { return Err(Error::Empty(name.to_string(), args[0].1.clone(), file_path.to_string())) }
+    if x.len() < min { return Err(Error::Domain(
+        format!("{name} needs at least {min} points, got {}", x.len()), args[0].1.clone(), file_path.to_string()
+    )) }
+    Ok((x, y))
+}
+fn singular(name: &str, pos: &Position, file_path: &str) -> Error {
+    Error::Domain(format!("{name} has no unique solution for this data"), pos.clone(), file_path.to_string())
+}
+fn polynomial(name: &str, x: &[f64], y: &[f64], deg: usize, pos: &Position, file_path: &str) -> Result<Value, Error> {
+    let a: Vec<Vec<f64>> = x.iter().map(|x| (0..=deg).map(|p| x.powi(p as i32)).collect()).collect();
+    let coeffs = least_squares(&a, y).ok_or_else(|| singular(name, pos, file_path))?;
+    let predicted: Vec<f64> = a.iter().map(|row| row.iter().zip(coeffs.iter()).map(|(a, c)| a * c).sum()).collect();
+    Ok(fit_result(&coeffs, y, &predicted))
+}
+fn evaluate(name: &str, func: &Value, x: f64, params: &[f64], pos: &Position, file_path: &str, context: &mut Context)
+-> Result<f64, Error> {
+    let mut args: Vec<(Value, Position)> = vec![(Value::Float(x), pos.clone())];
+    args.extend(params.iter().map(|p| (Value::Float(*p), pos.clone())));
+    let value = call(func, None, &args, pos, file_path, context)?;
+    value.number().ok_or_else(|| Error::IllegalValue(value, Type::Builtin(name.to_string()), pos.clone(), file_path.to_string()))
+}
+
+pub fn linfit(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("linfit", args, 2, pos, file_path)?;
+    let (x, y) = expect_xy("linfit", args, 2, file_path)?;
+    polynomial("linfit", &x, &y, 1, pos, file_path)
+}
+pub fn polyfit(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("polyfit", args, 3, pos, file_path)?;
+    let deg = expect_int("polyfit", &args[2], file_path)?;
+    if deg < 0 { return Err(Error::Domain(
+        format!("polyfit expects a non-negative degree, got {deg}"), args[2].1.clone(), file_path.to_string()
+    )) }
+    let (x, y) = expect_xy("polyfit", args, deg as usize + 1, file_path)?;
+    polynomial("polyfit", &x, &y, deg as usize, pos, file_path)
+}
+pub fn fit(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("fit", args, 4, pos, file_path)?;
+    let func = &args[0].0;
+    let Value::Function(params, _) = func else { return Err(illegal_arg("fit", &args[0], file_path)) };
+    let (x, y) = expect_xy("fit", &args[1..3], 1, file_path)?;
+    let mut p = expect_numbers("fit", &args[3], file_path)?;
+    if params.len() != p.len() + 1 {
+        return Err(Error::ArgumentCount(format!("{func}"), params.len(), p.len() + 1, args[3].1.clone(), file_path.to_string()))
+    }
+    let residuals = |p: &[f64], context: &mut Context| -> Result<Vec<f64>, Error> {
+        x.iter().zip(y.iter()).map(|(x, y)| Ok(y - evaluate("fit", func, *x, p, pos, file_path, context)?)).collect()
+    };
+    let cost = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>();
+    let mut r = residuals(&p, context)?;
+    let mut lambda = 1e-3;
+    for _ in 0..200 {
+        let mut jacobian: Vec<Vec<f64>> = vec![vec![0.0; p.len()]; x.len()];
+        for j in 0..p.len() {
+            let h = 1e-7 * p[j].abs().max(1.0);
+            let mut shifted = p.clone();
+            shifted[j] += h;
+            let r_shifted = residuals(&shifted, context)?;
+            for i in 0..x.len() { jacobian[i][j] = (r[i] - r_shifted[i]) / h }
+        }
+        let jtj: Vec<Vec<f64>> = (0..p.len()).map(|a| (0..p.len()).map(|b| {
+            jacobian.iter().map(|row| row[a] * row[b]).sum()
+        }).collect()).collect();
+        let jtr: Vec<f64> = (0..p.len()).map(|a| jacobian.iter().zip(r.iter()).map(|(row, r)| row[a] * r).sum()).collect();
+        let mut improved = false;
+        while lambda < 1e12 {
+            let mut damped = jtj.clone();
+            for (k, row) in damped.iter_mut().enumerate() { row[k] += lambda * jtj[k][k].max(1e-12) }
+            let Some(step) = solve_linear(damped, jtr.clone()) else { lambda *= 10.0; continue };
+            let candidate: Vec<f64> = p.iter().zip(step.iter()).map(|(p, s)| p + s).collect();
+            let r_candidate = residuals(&candidate, context)?;
+            if cost(&r_candidate) <= cost(&r) {
+                let converged = step.iter().zip(p.iter()).all(|(s, p)| s.abs() <= 1e-10 * (p.abs() + 1e-10))
+                || cost(&r) - cost(&r_candidate) <= 1e-15 * cost(&r);
+                p = candidate;
+                r = r_candidate;
+                lambda = (lambda / 10.0).max(1e-12);
+                improved = !converged;
+                break
+            }
+            lambda *= 10.0;
+        }
+        if !improved { break }
+    }
+    let predicted: Vec<f64> = y.iter().zip(r.iter()).map(|(y, r)| y - r).collect();
+    Ok(fit_result(&p, &y, &predicted))
+}
+pub fn model(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    if args.len() == 1 {
+        let coeffs = expect_numbers("model", &args[0], file_path)?;
+        return Ok(Value::Function(vec!["x".to_string()], poly_node(&coeffs, "x", pos)))
+    }
+    expect_args("model", args, 2, pos, file_path)?;
+    let Value::Function(params, body) = &args[0].0 else { return Err(illegal_arg("model", &args[0], file_path)) };
+    let values = expect_numbers("model", &args[1], file_path)?;
+    if params.len() != values.len() + 1 { return Err(Error::ArgumentCount(
+        format!("{}", args[0].0), params.len(), values.len() + 1, args[1].1.clone(), file_path.to_string()
+    )) }
+    let mut body = body.clone();
+    for (param, value) in params[1..].iter().zip(values.iter()) {
+        body = body.substitute(param, &number_node(*value));
+    }
+    Ok(Value::Function(vec![params[0].clone()], body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_recover_exact_models() {
+        assert_eq!(eval_lines(&["x : [0 1 2 3]", "linfit(x 2 * x + 1)"]).unwrap().to_string(), "[[1, 2], 1, [0, 0, 0, 0]]");
+        let Value::Vector(result) = eval_lines(&["e(x a b) : a * exp(b * x)", "x : [0 1 2 3]", "fit(e x 3 * exp(0.5 * x) [1 1])"]).unwrap()
+        else { panic!("expected a fit result") };
+        let Value::Vector(params) = &result[0] else { panic!("expected parameters") };
+        assert!((params[0].number().unwrap() - 3.0).abs() < 1e-8 && (params[1].number().unwrap() - 0.5).abs() < 1e-8);
+    }
+    #[test]
+    fn negative_zero_is_shown_as_zero() {
+        assert_eq!(eval_lines(&["linfit([1 2 3] [0 0 0])"]).unwrap().to_string(), "[[0, 0], 1, [0, 0, 0]]");
+        assert_eq!(eval_lines(&["e(x a b) : a * exp(b * x)", "model(e [0-0 2])"]).unwrap().to_string(), "function(x) : 0 * exp(2 * x)");
+    }
+}
\ No newline at end of file
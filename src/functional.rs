@@ -0,0 +1,104 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+
+fn truth(name: &str, value: Value, pos: &Position, file_path: &str) -> Result<bool, Error> {
+    match value.number() {
+        Some(number) => Ok(number != 0.0),
+        None => Err(Error::IllegalValue(value, Type::Builtin(name.to_string()), pos.clone(), file_path.to_string()))
+    }
+}
+
+pub fn map(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("map", args, 2, pos, file_path)?;
+    let func = expect_callable("map", &args[0], file_path)?;
+    let mut vector: Vec<Value> = vec![];
+    for value in expect_vector("map", &args[1], file_path)? {
+        vector.push(call(&func, None, &[(value.clone(), args[1].1.clone())], pos, file_path, context)?);
+    }
+    Ok(Value::Vector(vector))
+}
+pub fn predicate_filter(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("filter", args, 2, pos, file_path)?;
+    let func = expect_callable("filter", &args[0], file_path)?;
+    let mut vector: Vec<Value> = vec![];
+    for value in expect_vector("filter", &args[1], file_path)? {
+        let keep = call(&func, None, &[(value.clone(), args[1].1.clone())], pos, file_path, context)?;
+        if truth("filter", keep, pos, file_path)? { vector.push(value.clone()) }
+    }
+    Ok(Value::Vector(vector))
+}
+pub fn count(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("count", args, 2, pos, file_path)?;
+    let func = expect_callable("count", &args[0], file_path)?;
+    let mut count = 0;
+    for value in expect_vector("count", &args[1], file_path)? {
+        let keep = call(&func, None, &[(value.clone(), args[1].1.clone())], pos, file_path, context)?;
+        if truth("count", keep, pos, file_path)? { count += 1 }
+    }
+    Ok(Value::Int(count))
+}
+fn accumulate(name: &str, func: &Value, init: Value, vector: &(Value, Position), pos: &Position, file_path: &str,
+    context: &mut Context) -> Result<Vec<Value>, Error> {
+    let mut acc = init;
+    let mut steps: Vec<Value> = vec![];
+    for value in expect_vector(name, vector, file_path)? {
+        acc = call(func, None, &[(acc, pos.clone()), (value.clone(), vector.1.clone())], pos, file_path, context)?;
+        steps.push(acc.clone());
+    }
+    Ok(steps)
+}
+pub fn reduce(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("reduce", args, 2, pos, file_path)?;
+    let func = expect_callable("reduce", &args[0], file_path)?;
+    let vector = expect_vector("reduce", &args[1], file_path)?;
+    if vector.is_empty() { return Err(Error::Empty("reduce".to_string(), args[1].1.clone(), file_path.to_string())) }
+    let rest = (Value::Vector(vector[1..].to_vec()), args[1].1.clone());
+    let steps = accumulate("reduce", &func, vector[0].clone(), &rest, pos, file_path, context)?;
+    Ok(steps.last().cloned().unwrap_or(vector[0].clone()))
+}
+pub fn fold(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("fold", args, 3, pos, file_path)?;
+    let func = expect_callable("fold", &args[0], file_path)?;
+    let steps = accumulate("fold", &func, args[1].0.clone(), &args[2], pos, file_path, context)?;
+    Ok(steps.last().cloned().unwrap_or(args[1].0.clone()))
+}
+pub fn scan(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("scan", args, 3, pos, file_path)?;
+    let func = expect_callable("scan", &args[0], file_path)?;
+    Ok(Value::Vector(accumulate("scan", &func, args[1].0.clone(), &args[2], pos, file_path, context)?))
+}
+pub fn iterate(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("iterate", args, 3, pos, file_path)?;
+    let func = expect_callable("iterate", &args[0], file_path)?;
+    let n = expect_int("iterate", &args[2], file_path)?;
+    if n < 0 { return Err(Error::Domain(
+        format!("iterate expects a non-negative count, got {n}"), args[2].1.clone(), file_path.to_string()
+    )) }
+    let mut value = args[1].0.clone();
+    let mut vector: Vec<Value> = vec![value.clone()];
+    for _ in 0..n {
+        value = call(&func, None, &[(value, args[1].1.clone())], pos, file_path, context)?;
+        vector.push(value.clone());
+    }
+    Ok(Value::Vector(vector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_order_builtins_take_any_callable() {
+        assert_eq!(eval_lines(&["map(isprime [2 4])"]).unwrap().to_string(), "[true, false]");
+        assert_eq!(eval_lines(&["p : poly([0 1 1])", "map(p [1 2])"]).unwrap().to_string(), "[2, 6]");
+        assert_eq!(eval_lines(&["add(a b) : a + b", "scan(add 0 [1 2 3])"]).unwrap().to_string(), "[1, 3, 6]");
+        assert_eq!(eval_lines(&["sq(x) : x^2", "iterate(sq 2 3)"]).unwrap().to_string(), "[2, 4, 16, 256]");
+    }
+    #[test]
+    fn rejects_values_that_cannot_be_called() {
+        assert!(eval_lines(&["map(3 [1 2])"]).unwrap_err().to_string().starts_with("ERROR: int illegal for builtin(map)"));
+        assert!(eval_lines(&["add(a b) : a + b", "reduce(add [])"]).unwrap_err().to_string().starts_with("ERROR: reduce of empty vector"));
+    }
+}
\ No newline at end of file
@@ -1,22 +1,37 @@
-use std::cmp::min;
 use std::fmt::{Debug, Display, Formatter, Error as FMTError};
 use std::{fs, vec};
 use crate::error::*;
 use crate::position::*;
 use crate::lexer::*;
 use crate::parser::*;
-use std::f64::{consts::PI, INFINITY};
+use crate::builtins::*;
+use crate::number::*;
+use crate::vector::*;
+use crate::random::*;
+use crate::symbolic::*;
+use crate::roots::*;
+use crate::limit::*;
+use crate::complex::*;
+use crate::polynomial::*;
+use crate::dual::*;
+use std::f64::consts::PI;
 #[derive(Clone, PartialEq)]
 pub enum Type {
-    Int, Float, Vector, Function(String)
+    Int, Float, Bool, Vector, Function(Vec<String>), Builtin(String), Expr, Complex, Poly, Dual
 }
 impl Display for Type {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FMTError> {
         match &self {
             Self::Int => write!(f, "int"),
             Self::Float => write!(f, "float"),
+            Self::Bool => write!(f, "bool"),
             Self::Vector => write!(f, "vector"),
-            Self::Function(var) => write!(f, "function({var})"),
+            Self::Function(params) => write!(f, "function({})", params.join(" ")),
+            Self::Builtin(name) => write!(f, "builtin({name})"),
+            Self::Expr => write!(f, "expression"),
+            Self::Complex => write!(f, "complex"),
+            Self::Poly => write!(f, "polynomial"),
+            Self::Dual => write!(f, "dual"),
         }
     }
 }
@@ -27,15 +42,36 @@ impl Debug for Type {
 }
 #[derive(Clone, PartialEq)]
 pub enum Value {
-    Int(i64), Float(f64), Vector(Vec<Value>), Function(String, Node)
+    Int(i64), Float(f64), Bool(bool), Vector(Vec<Value>), Function(Vec<String>, Node), Builtin(String), Expr(Node),
+    Complex(Complex), Poly(Polynomial), Dual(Dual)
 }
 impl Value {
     pub fn type_(&self) -> Type {
         match self {
             Self::Int(_) => Type::Int,
             Self::Float(_) => Type::Float,
+            Self::Bool(_) => Type::Bool,
             Self::Vector(_) => Type::Vector,
-            Self::Function(var, _) => Type::Function(var.clone()),
+            Self::Function(params, _) => Type::Function(params.clone()),
+            Self::Builtin(name) => Type::Builtin(name.clone()),
+            Self::Expr(_) => Type::Expr,
+            Self::Complex(_) => Type::Complex,
+            Self::Poly(_) => Type::Poly,
+            Self::Dual(_) => Type::Dual,
+        }
+    }
+    pub fn number(&self) -> Option<f64> {
+        match self {
+            Self::Int(int) => Some(*int as f64),
+            Self::Float(float) => Some(*float),
+            Self::Bool(bool) => Some(*bool as i64 as f64),
+            _ => None
+        }
+    }
+    pub fn unbool(&self) -> Value {
+        match self {
+            Self::Bool(bool) => Self::Int(*bool as i64),
+            _ => self.clone()
         }
     }
 }
@@ -44,8 +80,14 @@ impl Display for Value {
         match &self {
             Self::Int(int) => write!(f, "{int}"),
             Self::Float(float) => write!(f, "{float}"),
+            Self::Bool(bool) => write!(f, "{bool}"),
             Self::Vector(vector) => write!(f, "{vector:?}"),
-            Self::Function(var, _) => write!(f, "function({var})"),
+            Self::Function(params, body) => write!(f, "function({}) : {body}", params.join(" ")),
+            Self::Builtin(name) => write!(f, "builtin({name})"),
+            Self::Expr(node) => write!(f, "{node}"),
+            Self::Complex(z) => write!(f, "{z}"),
+            Self::Poly(p) => write!(f, "{p}"),
+            Self::Dual(x) => write!(f, "{x}"),
         }
     }
 }
@@ -55,260 +97,196 @@ impl Debug for Value {
     }
 }
 
-pub fn binary(op: &Token, left: &Value, right: &Value, context: &mut Context) -> Result<Value, ()> {
-    match op {
-        Token::Add => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int(v1 + v2)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Float(*v1 as f64 + v2)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Float(v1 + *v2 as f64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Float(v1 + v2)),
-            (Value::Vector(v1), Value::Int(_)) => {
+pub fn binary(op: &Token, left: &Value, right: &Value, pos: &Position, file_path: &str) -> Result<Value, Error> {
+    let illegal = || Error::BinaryOperation(op.clone(), left.clone(), right.clone(), pos.clone(), file_path.to_string());
+    let overflow = || Error::Overflow(op.name().to_string(), pos.clone(), file_path.to_string());
+    let shape = |l1: usize, l2: usize| Error::Shape(l1, l2, pos.clone(), file_path.to_string());
+    if ![Token::Concat, Token::Hashtag].contains(op) {
+        if let (Value::Bool(_), _) | (_, Value::Bool(_)) = (left, right) {
+            return binary(op, &left.unbool(), &right.unbool(), pos, file_path)
+        }
+        match (left, right) {
+            (Value::Vector(v1), Value::Vector(v2)) => {
+                if v1.len() != v2.len() { return Err(shape(v1.len(), v2.len())) }
                 let mut vector: Vec<Value> = vec![];
-                for v in v1 {
-                    let value = binary(op, v, right, context)?;
-                    vector.push(value);
+                for (v1, v2) in v1.iter().zip(v2.iter()) {
+                    vector.push(binary(op, v1, v2, pos, file_path)?);
                 }
-                Ok(Value::Vector(vector))
+                return Ok(Value::Vector(vector))
             }
-            (Value::Vector(v1), Value::Float(_)) => {
+            (Value::Vector(v1), Value::Int(_) | Value::Float(_) | Value::Expr(_) | Value::Complex(_) | Value::Dual(_)) => {
                 let mut vector: Vec<Value> = vec![];
                 for v in v1 {
-                    let value = binary(op, v, right, context)?;
-                    vector.push(value);
+                    vector.push(binary(op, v, right, pos, file_path)?);
                 }
-                Ok(Value::Vector(vector))
+                return Ok(Value::Vector(vector))
             }
-            (Value::Vector(v1), Value::Vector(v2)) => {
+            (Value::Int(_) | Value::Float(_) | Value::Expr(_) | Value::Complex(_) | Value::Dual(_), Value::Vector(v2)) => {
                 let mut vector: Vec<Value> = vec![];
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    vector.push(value);
+                for v in v2 {
+                    vector.push(binary(op, left, v, pos, file_path)?);
                 }
-                Ok(Value::Vector(vector))
+                return Ok(Value::Vector(vector))
             }
-            _ => Err(())
+            (Value::Expr(_), _) | (_, Value::Expr(_)) => return symbolic_binary(op, left, right, pos).ok_or_else(illegal),
+            (Value::Poly(_), _) | (_, Value::Poly(_)) => return poly_binary(op, left, right, pos, file_path)?.ok_or_else(illegal),
+            (Value::Complex(_), _) | (_, Value::Complex(_)) => return complex_binary(op, left, right).ok_or_else(illegal),
+            (Value::Dual(_), _) | (_, Value::Dual(_)) => return dual_binary(op, left, right).ok_or_else(illegal),
+            _ => {}
+        }
+    }
+    match op {
+        Token::Add => match (left, right) {
+            (Value::Int(v1), Value::Int(v2)) => v1.checked_add(*v2).map(Value::Int).ok_or_else(overflow),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Float(*v1 as f64 + v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Float(v1 + *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Float(v1 + v2)),
+            _ => Err(illegal())
         }
         Token::Subtract => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int(v1 - v2)),
+            (Value::Int(v1), Value::Int(v2)) => v1.checked_sub(*v2).map(Value::Int).ok_or_else(overflow),
             (Value::Int(v1), Value::Float(v2)) => Ok(Value::Float(*v1 as f64 - v2)),
             (Value::Float(v1), Value::Int(v2)) => Ok(Value::Float(v1 - *v2 as f64)),
             (Value::Float(v1), Value::Float(v2)) => Ok(Value::Float(v1 - v2)),
-            (Value::Vector(v1), Value::Int(_)) => {
-                let mut vector: Vec<Value> = vec![];
-                for v in v1 {
-                    let value = binary(op, v, right, context)?;
-                    vector.push(value);
-                }
-                Ok(Value::Vector(vector))
-            }
-            (Value::Vector(v1), Value::Float(_)) => {
-                let mut vector: Vec<Value> = vec![];
-                for v in v1 {
-                    let value = binary(op, v, right, context)?;
-                    vector.push(value);
-                }
-                Ok(Value::Vector(vector))
-            }
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                let mut vector: Vec<Value> = vec![];
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    vector.push(value);
-                }
-                Ok(Value::Vector(vector))
-            }
-            _ => Err(())
+            _ => Err(illegal())
         }
         Token::Multiply => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int(v1 * v2)),
+            (Value::Int(v1), Value::Int(v2)) => v1.checked_mul(*v2).map(Value::Int).ok_or_else(overflow),
             (Value::Int(v1), Value::Float(v2)) => Ok(Value::Float(*v1 as f64 * v2)),
             (Value::Float(v1), Value::Int(v2)) => Ok(Value::Float(v1 * *v2 as f64)),
             (Value::Float(v1), Value::Float(v2)) => Ok(Value::Float(v1 * v2)),
-            (Value::Vector(v1), Value::Int(_)) => {
-                let mut vector: Vec<Value> = vec![];
-                for v in v1 {
-                    let value = binary(op, v, right, context)?;
-                    vector.push(value);
-                }
-                Ok(Value::Vector(vector))
-            }
-            (Value::Vector(v1), Value::Float(_)) => {
-                let mut vector: Vec<Value> = vec![];
-                for v in v1 {
-                    let value = binary(op, v, right, context)?;
-                    vector.push(value);
-                }
-                Ok(Value::Vector(vector))
-            }
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                let mut vector: Vec<Value> = vec![];
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    vector.push(value);
-                }
-                Ok(Value::Vector(vector))
+            _ => Err(illegal())
+        }
+        Token::Power => match (left, right) {
+            (Value::Int(v1), Value::Int(v2)) => {
+                if *v2 < 0 { return Ok(Value::Float((*v1 as f64).powf(*v2 as f64))) }
+                u32::try_from(*v2).ok().and_then(|v2| v1.checked_pow(v2)).map(Value::Int).ok_or_else(overflow)
             }
-            _ => Err(())
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Float((*v1 as f64).powf(*v2))),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Float(v1.powf(*v2 as f64))),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Float(v1.powf(*v2))),
+            _ => Err(illegal())
         }
         Token::Divide => {
-            if (left, right) == (&Value::Float(INFINITY), &Value::Float(INFINITY)) {
-                return Ok(Value::Float(INFINITY))
+            if (left, right) == (&Value::Float(f64::INFINITY), &Value::Float(f64::INFINITY)) {
+                return Ok(Value::Float(f64::INFINITY))
             }
             match (left, right) {
                 (Value::Int(v1), Value::Int(v2)) => Ok(Value::Float(*v1 as f64 / *v2 as f64)),
                 (Value::Int(v1), Value::Float(v2)) => Ok(Value::Float(*v1 as f64 / v2)),
                 (Value::Float(v1), Value::Int(v2)) => Ok(Value::Float(v1 / *v2 as f64)),
                 (Value::Float(v1), Value::Float(v2)) => Ok(Value::Float(v1 / v2)),
-                (Value::Vector(v1), Value::Int(_)) => {
-                    let mut vector: Vec<Value> = vec![];
-                    for v in v1 {
-                        let value = binary(op, v, right, context)?;
-                        vector.push(value);
-                    }
-                    Ok(Value::Vector(vector))
-                }
-                (Value::Vector(v1), Value::Float(_)) => {
-                    let mut vector: Vec<Value> = vec![];
-                    for v in v1 {
-                        let value = binary(op, v, right, context)?;
-                        vector.push(value);
-                    }
-                    Ok(Value::Vector(vector))
-                }
-                (Value::Vector(v1), Value::Vector(v2)) => {
-                    let mut vector: Vec<Value> = vec![];
-                    for i in 0..min(v1.len(), v2.len()) {
-                        let value = binary(op, &v1[i], &v2[i], context)?;
-                        vector.push(value);
-                    }
-                    Ok(Value::Vector(vector))
-                }
-                _ => Err(())
+                _ => Err(illegal())
             }
         }
+        Token::Concat => match (left, right) {
+            (Value::Vector(v1), Value::Vector(v2)) => Ok(Value::Vector([v1.clone(), v2.clone()].concat())),
+            (Value::Vector(v1), _) if right.number().is_some() => Ok(Value::Vector([v1.clone(), vec![right.clone()]].concat())),
+            (_, Value::Vector(v2)) if left.number().is_some() => Ok(Value::Vector([vec![left.clone()], v2.clone()].concat())),
+            _ if left.number().is_some() && right.number().is_some() => Ok(Value::Vector(vec![left.clone(), right.clone()])),
+            _ => Err(illegal())
+        }
         Token::Hashtag => match (left, right) {
             (Value::Vector(vector), Value::Int(index)) => {
-                if (*index as usize) >= vector.len() { return Err(()) }
-                Ok(vector[*index as usize].clone())
+                Ok(vector[vector_index(vector.len(), *index, pos, file_path)?].clone())
             }
-            _ => Err(())
-        }
-        Token::Equal => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int((v1 == v2) as i64)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Int((*v1 as f64 == *v2) as i64)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Int((*v1 == *v2 as f64) as i64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Int((v1 == v2) as i64)),
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                if v1.len() != v2.len() { return Ok(Value::Int(0)) }
-                let mut equal = true;
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    if let Value::Int(int) = value { equal = int != 0; }
-                    if !equal { break }
+            (Value::Vector(vector), Value::Vector(indices)) => {
+                let mut values: Vec<Value> = vec![];
+                for index in &mask_indices(vector.len(), indices, pos, file_path)? {
+                    if let Value::Int(index) = index {
+                        values.push(vector[vector_index(vector.len(), *index, pos, file_path)?].clone());
+                        continue
+                    }
+                    return Err(illegal())
                 }
-                Ok(Value::Int(equal as i64))
+                Ok(Value::Vector(values))
             }
-            _ => Err(())
+            _ => Err(illegal())
+        }
+        Token::Equal => match (left, right) {
+            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Bool(v1 == v2)),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Bool(*v1 as f64 == *v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Bool(*v1 == *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Bool(v1 == v2)),
+            _ => Err(illegal())
         }
         Token::NotEqual => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int((v1 != v2) as i64)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Int((*v1 as f64 != *v2) as i64)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Int((*v1 != *v2 as f64) as i64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Int((v1 != v2) as i64)),
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                if v1.len() != v2.len() { return Ok(Value::Int(1)) }
-                let mut equal = true;
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(&Token::Equal, &v1[i], &v2[i], context)?;
-                    if let Value::Int(int) = value { equal = int != 0; }
-                    if !equal { break }
-                }
-                Ok(Value::Int(!equal as i64))
-            }
-            _ => Err(())
+            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Bool(v1 != v2)),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Bool(*v1 as f64 != *v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Bool(*v1 != *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Bool(v1 != v2)),
+            _ => Err(illegal())
         }
         Token::Less => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int((v1 < v2) as i64)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Int(((*v1 as f64) < *v2) as i64)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Int((*v1 < *v2 as f64) as i64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Int((v1 < v2) as i64)),
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                let mut equal = true;
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    if let Value::Int(int) = value { equal = int != 0; }
-                    if !equal { break }
-                }
-                Ok(Value::Int(equal as i64))
-            }
-            _ => Err(())
+            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Bool(v1 < v2)),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Bool((*v1 as f64) < *v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Bool(*v1 < *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Bool(v1 < v2)),
+            _ => Err(illegal())
         }
         Token::Greater => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int((v1 > v2) as i64)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Int((*v1 as f64 > *v2) as i64)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Int((*v1 > *v2 as f64) as i64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Int((v1 > v2) as i64)),
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                let mut equal = true;
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    if let Value::Int(int) = value { equal = int != 0; }
-                    if !equal { break }
-                }
-                Ok(Value::Int(equal as i64))
-            }
-            _ => Err(())
+            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Bool(v1 > v2)),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Bool(*v1 as f64 > *v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Bool(*v1 > *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Bool(v1 > v2)),
+            _ => Err(illegal())
         }
         Token::LessEqual => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int((v1 <= v2) as i64)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Int(((*v1 as f64) <= *v2) as i64)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Int((*v1 <= *v2 as f64) as i64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Int((v1 <= v2) as i64)),
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                let mut equal = true;
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    if let Value::Int(int) = value { equal = int != 0; }
-                    if !equal { break }
-                }
-                Ok(Value::Int(equal as i64))
-            }
-            _ => Err(())
+            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Bool(v1 <= v2)),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Bool((*v1 as f64) <= *v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Bool(*v1 <= *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Bool(v1 <= v2)),
+            _ => Err(illegal())
         }
         Token::GreaterEqual => match (left, right) {
-            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Int((v1 >= v2) as i64)),
-            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Int((*v1 as f64 >= *v2) as i64)),
-            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Int((*v1 >= *v2 as f64) as i64)),
-            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Int((v1 >= v2) as i64)),
-            (Value::Vector(v1), Value::Vector(v2)) => {
-                let mut equal = true;
-                for i in 0..min(v1.len(), v2.len()) {
-                    let value = binary(op, &v1[i], &v2[i], context)?;
-                    if let Value::Int(int) = value { equal = int != 0; }
-                    if !equal { break }
-                }
-                Ok(Value::Int(equal as i64))
-            }
-            _ => Err(())
+            (Value::Int(v1), Value::Int(v2)) => Ok(Value::Bool(v1 >= v2)),
+            (Value::Int(v1), Value::Float(v2)) => Ok(Value::Bool(*v1 as f64 >= *v2)),
+            (Value::Float(v1), Value::Int(v2)) => Ok(Value::Bool(*v1 >= *v2 as f64)),
+            (Value::Float(v1), Value::Float(v2)) => Ok(Value::Bool(v1 >= v2)),
+            _ => Err(illegal())
         }
-        _ => Err(())
+        _ => Err(illegal())
     }
 }
-pub fn unary(op: &Token, value: &Value, context: &mut Context) -> Result<Value, ()> {
+pub fn unary(op: &Token, value: &Value, pos: &Position, file_path: &str) -> Result<Value, Error> {
+    let illegal = || Error::UnaryOperation(op.clone(), value.clone(), pos.clone(), file_path.to_string());
+    let overflow = || Error::Overflow(op.name().to_string(), pos.clone(), file_path.to_string());
+    if let Value::Expr(node) = value {
+        return Ok(expression(simplify_node(&Node::Unary(op.clone(), (Box::new(node.clone()), pos.clone())), pos)))
+    }
     match op {
         Token::Subtract => match &value {
-            Value::Int(v) => Ok(Value::Int(-v)),
+            Value::Int(v) => v.checked_neg().map(Value::Int).ok_or_else(overflow),
             Value::Float(v) => Ok(Value::Float(-v)),
+            Value::Complex(z) => Ok(Value::Complex(-*z)),
+            Value::Poly(p) => Ok(Value::Poly(p.scale(-1.0))),
+            Value::Dual(x) => Ok(Value::Dual(-x.clone())),
             Value::Vector(v) => {
                 let mut vector: Vec<Value> = vec![];
                 for value in v {
-                    vector.push(unary(op, value, context)?);
+                    vector.push(unary(op, value, pos, file_path)?);
                 }
                 Ok(Value::Vector(vector))
             }
-            _ => Err(())
+            _ => Err(illegal())
         }
-        _ => Err(())
+        Token::Factorial => match &value {
+            Value::Int(v) => {
+                if *v < 0 { return Err(Error::Domain(
+                    format!("factorial of negative number {v}"), pos.clone(), file_path.to_string()
+                )) }
+                factorial(*v).map(Value::Int).ok_or_else(overflow)
+            }
+            Value::Vector(v) => {
+                let mut vector: Vec<Value> = vec![];
+                for value in v {
+                    vector.push(unary(op, value, pos, file_path)?);
+                }
+                Ok(Value::Vector(vector))
+            }
+            _ => Err(illegal())
+        }
+        _ => Err(illegal())
     }
 }
 
@@ -317,24 +295,46 @@ pub fn interpret(node_and_pos: (&Node, &Position), file_path: &str, context: &mu
     match node {
         Node::Int(int) => Ok(Value::Int(*int)),
         Node::Float(float) => Ok(Value::Float(*float)),
-        Node::Infinity => Ok(Value::Float(INFINITY)),
+        Node::Infinity => Ok(Value::Float(f64::INFINITY)),
         Node::PI => Ok(Value::Float(PI)),
         Node::Variable(var) => {
-            let value = context.get(var);
-            if value.is_none() { return Err(Error::Variable(var.clone(), node_pos.clone(), file_path.to_string())) }
-            return Ok(value.unwrap())
+            if let Some(value) = context.get(var) { return Ok(value) }
+            if builtin(var).is_some() { return Ok(Value::Builtin(var.clone())) }
+            if context.symbolic { return Ok(Value::Expr(Node::Variable(var.clone()))) }
+            Err(Error::Variable(var.clone(), node_pos.clone(), file_path.to_string()))
         }
         Node::Set((var_node, var_pos), (expr_node, expr_pos)) => {
-            if let Node::Call((call_var_node, _), (call_expr_node, call_expr_pos)) = var_node.as_ref() {
+            if let Node::Call((call_var_node, call_var_pos), call_arg_nodes) = var_node.as_ref() {
                 if let Node::Variable(var) = call_var_node.as_ref() {
-                    if let Node::Variable(param) = call_expr_node.as_ref() {
-                        let value = Value::Function(param.clone(), expr_node.as_ref().clone());
-                        context.set(var, &value);
-                        return Ok(value)
+                    let mut params: Vec<String> = vec![];
+                    for (arg_node, arg_pos) in call_arg_nodes {
+                        if let Node::Variable(param) = arg_node {
+                            params.push(param.clone());
+                            continue
+                        }
+                        return Err(Error::ExpectNode(Node::Variable(
+                            "".to_string()), arg_node.clone(), arg_pos.clone(), file_path.to_string()
+                        ))
                     }
+                    let value = Value::Function(params, expr_node.as_ref().clone());
+                    context.set(var, &value);
+                    return Ok(value)
+                }
+                return Err(Error::ExpectNode(Node::Variable(
+                    "".to_string()), call_var_node.as_ref().clone(), call_var_pos.clone(), file_path.to_string()
+                ))
+            }
+            if let Node::Binary(Token::Hashtag, (target_node, target_pos), (index_node, index_pos)) = var_node.as_ref() {
+                if let Node::Variable(var) = target_node.as_ref() {
+                    let target = interpret((target_node.as_ref(), target_pos), file_path, context)?;
+                    let index = index(&target, (index_node, index_pos), file_path, context)?;
+                    let value = interpret((expr_node.as_ref(), expr_pos), file_path, context)?;
+                    let vector = assign_index(&target, &(index, index_pos.clone()), &(value, expr_pos.clone()), file_path)?;
+                    context.set(var, &vector);
+                    return Ok(vector)
                 }
                 return Err(Error::ExpectNode(Node::Variable(
-                    "".to_string()), call_expr_node.as_ref().clone(), call_expr_pos.clone(), file_path.to_string()
+                    "".to_string()), target_node.as_ref().clone(), target_pos.clone(), file_path.to_string()
                 ))
             }
             let value = interpret((expr_node.as_ref(), expr_pos), file_path, context)?;
@@ -346,21 +346,21 @@ pub fn interpret(node_and_pos: (&Node, &Position), file_path: &str, context: &mu
                 "".to_string()), var_node.as_ref().clone(), var_pos.clone(), file_path.to_string()
             ))
         }
-        Node::Call((var_node, var_pos), (expr_node, expr_pos)) => {
-            let value = interpret((expr_node.as_ref(), expr_pos), file_path, context)?;
+        Node::Call((var_node, var_pos), arg_nodes) => {
+            let mut args: Vec<(Value, Position)> = vec![];
+            for (arg_node, arg_pos) in arg_nodes {
+                args.push((interpret((arg_node, arg_pos), file_path, context)?, arg_pos.clone()));
+            }
             let func = interpret((var_node.as_ref(), var_pos), file_path, context)?;
-            if let Node::Variable(var) = var_node.as_ref() {
-                if let Value::Function(param, body) = &func {
-                    let mut context_ = Context::new();
-                    context_.set(var, &func);
-                    context_.set(param, &value);
-                    let value = interpret((body, var_pos), file_path, &mut context_)?;
-                    return Ok(value)
+            match &func {
+                Value::Function(_, _) | Value::Builtin(_) | Value::Poly(_) => {
+                    let name = if let Node::Variable(var) = var_node.as_ref() { Some(var) } else { None };
+                    call(&func, name, &args, node_pos, file_path, context)
                 }
+                _ => Err(Error::ExpectNode(Node::Variable(
+                    "".to_string()), var_node.as_ref().clone(), var_pos.clone(), file_path.to_string()
+                ))
             }
-            Err(Error::ExpectNode(Node::Variable(
-                "".to_string()), var_node.as_ref().clone(), var_pos.clone(), file_path.to_string()
-            ))
         }
         Node::Vector(nodes) => {
             let mut vector: Vec<Value> = vec![];
@@ -369,45 +369,129 @@ pub fn interpret(node_and_pos: (&Node, &Position), file_path: &str, context: &mu
                 match &value {
                     Value::Int(_) => {},
                     Value::Float(_) => {},
+                    Value::Bool(_) => {},
+                    Value::Vector(_) => {},
+                    Value::Expr(_) => {},
+                    Value::Complex(_) => {},
+                    Value::Poly(_) => {},
+                    Value::Dual(_) => {},
                     _ => return Err(Error::IllegalValue(value, Type::Vector, n.1.clone(), file_path.to_string()))
                 }
                 vector.push(value);
             }
             Ok(Value::Vector(vector))
         }
+        Node::Range((start_node, start_pos), (end_node, end_pos), step) => {
+            let start = interpret((start_node.as_ref(), start_pos), file_path, context)?;
+            let end = interpret((end_node.as_ref(), end_pos), file_path, context)?;
+            let step = match step {
+                Some((step_node, step_pos)) =>
+                Some((interpret((step_node.as_ref(), step_pos), file_path, context)?, step_pos.clone())),
+                None => None
+            };
+            range(&(start, start_pos.clone()), &(end, end_pos.clone()), step.as_ref(), node_pos, file_path)
+        }
+        Node::Binary(Token::Hashtag, (left_node, left_pos), (right_node, right_pos)) => {
+            let left = interpret((left_node.as_ref(), left_pos), file_path, context)?;
+            let right = index(&left, (right_node, right_pos), file_path, context)?;
+            binary(&Token::Hashtag, &left, &right, node_pos, file_path)
+        }
+        Node::Series(op, var, (values_node, values_pos), (body_node, body_pos)) => {
+            let values = match interpret((values_node.as_ref(), values_pos), file_path, context)? {
+                Value::Vector(values) => values,
+                value => vec![value]
+            };
+            let (mut total, op) = match op {
+                Token::Product => (Value::Int(1), Token::Multiply),
+                _ => (Value::Int(0), Token::Add)
+            };
+            let mut scope = context.clone();
+            for value in values {
+                scope.set(var, &value);
+                let term = interpret((body_node.as_ref(), body_pos), file_path, &mut scope)?;
+                total = binary(&op, &total, &term, node_pos, file_path)?;
+            }
+            context.rng = scope.rng;
+            Ok(total)
+        }
+        Node::Diff((func_node, func_pos), var) => {
+            let func = interpret((func_node.as_ref(), func_pos), file_path, context)?;
+            differentiate("diff", &(func, func_pos.clone()), Some(var), file_path, context)
+        }
+        Node::Solve(args) => solve(args, node_pos, file_path, context),
+        Node::Limit(name, args) => limit(name, args, node_pos, file_path, context),
+        Node::Binary(Token::Where, (left_node, left_pos), (right_node, right_pos)) => {
+            let value = interpret((left_node.as_ref(), left_pos), file_path, context)?;
+            let bindings = match right_node.as_ref() {
+                Node::Vector(nodes) => nodes.clone(),
+                node => vec![(node.clone(), right_pos.clone())]
+            };
+            substitute(value, &bindings, node_pos, file_path, context)
+        }
         Node::Binary(op, left_node, right_node) => {
             let left = interpret((left_node.0.as_ref(), &left_node.1), file_path, context)?;
             let right = interpret((right_node.0.as_ref(), &right_node.1), file_path, context)?;
-            let res = binary(op, &left, &right, context);
-            if res.is_err() {
-                if op == &Token::Hashtag {
-                    if let (Value::Vector(vector), Value::Int(index)) = (&left, &right) {
-                        return Err(Error::Index(vector.len()-1, *index as usize, node_pos.clone(), file_path.to_string()))
-                    }
-                }
-                return Err(Error::BinaryOperation(
-                    op.clone(), left, right, node_pos.clone(), file_path.to_string()
-                ))
-            }
-            Ok(res.unwrap())
+            binary(op, &left, &right, node_pos, file_path)
         }
         Node::Unary(op, n) => {
             let value = interpret((n.0.as_ref(), &n.1), file_path, context)?;
-            let res = unary(op, &value, context);
-            if res.is_err() { return Err(Error::UnaryOperation(
-                op.clone(), value, node_pos.clone(), file_path.to_string()
+            unary(op, &value, node_pos, file_path)
+        }
+    }
+}
+
+// ranges after # are half-open slices, so v # 1..3 takes the elements at 1 and 2
+fn index(target: &Value, (node, pos): (&Node, &Position), file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    let (Value::Vector(vector), Node::Range((start_node, start_pos), (end_node, end_pos), step)) = (target, node) else {
+        return interpret((node, pos), file_path, context)
+    };
+    let start = interpret((start_node.as_ref(), start_pos), file_path, context)?;
+    let end = interpret((end_node.as_ref(), end_pos), file_path, context)?;
+    let step = match step {
+        Some((step_node, step_pos)) => Some((interpret((step_node.as_ref(), step_pos), file_path, context)?, step_pos.clone())),
+        None => None
+    };
+    slice(vector.len(), &(start, start_pos.clone()), &(end, end_pos.clone()), step.as_ref(), pos, file_path)
+}
+
+pub fn call(func: &Value, name: Option<&String>, args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context)
+-> Result<Value, Error> {
+    match func {
+        Value::Function(params, body) => {
+            if params.len() != args.len() { return Err(Error::ArgumentCount(
+                format!("{func}"), params.len(), args.len(), pos.clone(), file_path.to_string()
             )) }
-            Ok(res.unwrap())
+            let mut context_ = context.scope();
+            if let Some(name) = name { context_.set(name, func); }
+            for (param, (value, _)) in params.iter().zip(args.iter()) {
+                context_.set(param, value);
+            }
+            let value = interpret((body, pos), file_path, &mut context_);
+            context.rng = context_.rng;
+            value
+        }
+        Value::Builtin(name) => match builtin(name) {
+            Some(func) => func(args, pos, file_path, context),
+            None => Err(Error::Variable(name.clone(), pos.clone(), file_path.to_string()))
         }
+        Value::Poly(p) => {
+            if args.len() != 1 { return Err(Error::ArgumentCount(format!("{p}"), 1, args.len(), pos.clone(), file_path.to_string())) }
+            poly_eval(p, &args[0], file_path)
+        }
+        _ => Err(Error::IllegalValue(func.clone(), Type::Function(vec![]), pos.clone(), file_path.to_string()))
     }
 }
 
 #[derive(Clone)]
 pub struct Context {
-    pub stack: Vec<(String, Value)>
+    pub stack: Vec<(String, Value)>,
+    pub rng: Rng,
+    pub symbolic: bool
 }
 impl Context {
-    pub fn new() -> Self { Self { stack: vec![] } }
+    pub fn new() -> Self { Self { stack: vec![], rng: Rng::from_time(), symbolic: false } }
+    pub fn seeded(seed: u64) -> Self { Self { stack: vec![], rng: Rng::new(seed), symbolic: false } }
+    pub fn scope(&self) -> Self { Self { stack: vec![], rng: self.rng.clone(), symbolic: self.symbolic } }
     pub fn set(&mut self, var: &String, value: &Value) {
         for reg in self.stack.iter_mut() {
             if &reg.0 == var { reg.1 = value.clone(); return }
@@ -445,15 +529,50 @@ pub fn run(text: &str, file_path: &str, context: &mut Context) -> Option<Value>
         return None
     }
     let value = res.unwrap();
-    return Some(value)
+    Some(value)
 }
-pub fn runfile(file_path: &str) {
+pub fn runfile(file_path: &str, context: &mut Context) {
     let res = fs::read_to_string(file_path);
     if res.is_err() {
         println!("{}", res.err().unwrap());
         return
     }
     let text = res.unwrap();
-    let mut context = Context::new();
-    run(text.as_str(), file_path, &mut context);
+    run(text.as_str(), file_path, context);
+}
+
+// runs lines in a fresh seeded context and returns the value of the last one
+#[cfg(test)]
+pub fn eval_lines(lines: &[&str]) -> Result<Value, Error> {
+    let mut context = Context::seeded(0);
+    let mut value = Value::Int(0);
+    for line in lines {
+        let (node, pos) = parse(lex(&format!("{line}\n"), "<test>")?, "<test>")?;
+        value = interpret((&node, &pos), "<test>", &mut context)?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(values: &[i64]) -> Value { Value::Vector(values.iter().map(|v| Value::Int(*v)).collect()) }
+
+    #[test]
+    fn scalars_broadcast_over_vectors() {
+        let pos = Position::new(0, 0, 0, 0, 0, 0);
+        assert_eq!(binary(&Token::Multiply, &Value::Int(2), &ints(&[1, 2, 3]), &pos, "<test>").unwrap().to_string(), "[2, 4, 6]");
+        assert_eq!(eval_lines(&["[1 2 3] - 1"]).unwrap().to_string(), "[0, 1, 2]");
+        assert_eq!(eval_lines(&["10 / [2 4]"]).unwrap().to_string(), "[5, 2.5]");
+        assert_eq!(eval_lines(&["2 ^ [1 2 3]"]).unwrap().to_string(), "[2, 4, 8]");
+        assert_eq!(eval_lines(&["[[1 2] [3 4]] + 1"]).unwrap().to_string(), "[[2, 3], [4, 5]]");
+    }
+    #[test]
+    fn vectors_combine_elementwise_only_at_equal_lengths() {
+        let pos = Position::new(0, 0, 0, 0, 0, 0);
+        assert_eq!(binary(&Token::Add, &ints(&[1, 2]), &ints(&[10, 20]), &pos, "<test>").unwrap().to_string(), "[11, 22]");
+        assert!(matches!(binary(&Token::Add, &ints(&[1, 2]), &ints(&[1, 2, 3]), &pos, "<test>"), Err(Error::Shape(2, 3, _, _))));
+        assert!(matches!(eval_lines(&["[1 2 3] * [1 2]"]), Err(Error::Shape(3, 2, _, _))));
+    }
 }
\ No newline at end of file
@@ -30,6 +30,8 @@ pub enum Token {
     Less,
     #[token("+")]
     Add,
+    #[token("++")]
+    Concat,
     #[token("-")]
     Subtract,
     #[token("*")]
@@ -42,8 +44,18 @@ pub enum Token {
     Modulo,
     #[token("#")]
     Hashtag,
+    #[token("!")]
+    Factorial,
     #[token(":")]
     Rep,
+    #[token("|")]
+    Where,
+    #[token("..")]
+    Range,
+    #[token("Σ")]
+    Sum,
+    #[token("Π")]
+    Product,
     
     #[token("(")]
     GroupIn,
@@ -87,13 +99,19 @@ impl Token {
             Self::Greater => "'>'",
             Self::Less => "'<'",
             Self::Add => "'+'",
+            Self::Concat => "'++'",
             Self::Subtract => "'-'",
             Self::Multiply => "'*'",
             Self::Divide => "'/'",
             Self::Power => "'^'",
             Self::Modulo => "'%'",
             Self::Hashtag => "'#'",
+            Self::Factorial => "'!'",
             Self::Rep => "':'",
+            Self::Where => "'|'",
+            Self::Range => "'..'",
+            Self::Sum => "'Σ'",
+            Self::Product => "'Π'",
             Self::GroupIn => "'('",
             Self::GroupOut => "')'",
             Self::VectorIn => "'['",
@@ -103,6 +121,9 @@ impl Token {
             Self::TypeEq => "'is'",
         }
     }
+    pub fn symbol(&self) -> &str {
+        self.name().trim_matches('\'')
+    }
 }
 
 pub fn lex(text: &str, file_path: &str) -> Result<Vec<(Token, Position)>, Error> {
@@ -0,0 +1,159 @@
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::parser::*;
+use crate::interpreter::*;
+use crate::symbolic::*;
+
+type Approach<'a> = dyn FnMut(f64) -> Result<f64, Error> + 'a;
+
+fn snap(x: f64) -> f64 {
+    (1..=100).map(|den| (x * den as f64).round() / den as f64).find(|y| (y - x).abs() <= 1e-9 * (1.0 + x.abs())).unwrap_or(x) + 0.0
+}
+fn at(node: &Node, var: &String, x: f64, pos: &Position, file_path: &str, scope: &mut Context) -> Result<f64, Error> {
+    scope.set(var, &Value::Float(x));
+    match interpret((node, pos), file_path, scope) {
+        Ok(value) => Ok(value.number().unwrap_or(f64::NAN)),
+        Err(Error::Domain(_, _, _)) => Ok(f64::NAN),
+        Err(error) => Err(error)
+    }
+}
+fn clean(node: &Node, var: &String, x: f64, pos: &Position, file_path: &str, scope: &mut Context) -> Result<bool, Error> {
+    let children: Vec<&Node> = match node {
+        Node::Binary(_, (left, _), (right, _)) => vec![left, right],
+        Node::Unary(_, (node, _)) => vec![node],
+        Node::Call(_, args) => args.iter().map(|(node, _)| node).collect(),
+        _ => vec![]
+    };
+    for child in children {
+        if !clean(child, var, x, pos, file_path, scope)? { return Ok(false) }
+    }
+    Ok(at(node, var, x, pos, file_path, scope)?.is_finite())
+}
+
+// -- SYMBOLIC -------------------------------------------------------------------------
+
+fn lhopital(node: &Node, var: &String, a: f64, pos: &Position, file_path: &str, context: &mut Context) -> Result<Option<f64>, Error> {
+    if derivative(node, var, pos, file_path, context).is_err() { return Ok(None) }
+    let mut scope = context.scope();
+    let mut node = node.clone();
+    for _ in 0..8 {
+        if clean(&node, var, a, pos, file_path, &mut scope)? { return Ok(Some(at(&node, var, a, pos, file_path, &mut scope)?)) }
+        let Node::Binary(Token::Divide, (num, _), (den, _)) = &node else { return Ok(None) };
+        let (n, d) = (at(num, var, a, pos, file_path, &mut scope)?, at(den, var, a, pos, file_path, &mut scope)?);
+        if !(n == 0.0 && d == 0.0 || n.is_infinite() && d.is_infinite()) { return Ok(None) }
+        let num = simplify_node(&derivative(num, var, pos, file_path, context)?, pos);
+        let den = simplify_node(&derivative(den, var, pos, file_path, context)?, pos);
+        node = binary_node(Token::Divide, num, den, pos);
+    }
+    Ok(None)
+}
+
+// -- NUMERIC --------------------------------------------------------------------------
+
+fn richardson(f: &mut Approach, start: f64) -> Result<Option<f64>, Error> {
+    let mut table: Vec<Vec<f64>> = vec![];
+    let (mut best, mut error) = (f64::NAN, f64::INFINITY);
+    for i in 0..20 {
+        let mut row = vec![f(start / 2f64.powi(i as i32))?];
+        if i > 0 && (row[0] - table[i - 1][0]).abs() <= error { (best, error) = (row[0], (row[0] - table[i - 1][0]).abs()) }
+        for j in 1..=i {
+            let (factor, prev) = (2f64.powi(j as i32), table[i - 1][j - 1]);
+            row.push((factor * row[j - 1] - prev) / (factor - 1.0));
+            let estimate = (row[j] - row[j - 1]).abs().max((row[j] - prev).abs());
+            if estimate <= error { (best, error) = (row[j], estimate) }
+        }
+        let stalled = i > 0 && (row[i] - table[i - 1][i - 1]).abs() >= 2.0 * error;
+        table.push(row);
+        if stalled && error <= 1e-8 * (1.0 + best.abs()) { break }
+    }
+    Ok((error <= 1e-8 * (1.0 + best.abs())).then_some(best))
+}
+fn extrapolate(f: &mut Approach, smallest: f64) -> Result<Option<f64>, Error> {
+    // a second sequence of steps must confirm the first, chance agreement in an oscillating table is rejected
+    if let Some(best) = richardson(f, 0.1)? {
+        let again = richardson(f, 0.0613)?;
+        return Ok(again.filter(|again| (again - best).abs() <= 1e-6 * (1.0 + best.abs())).map(|_| snap(best)))
+    }
+    let tail = [1e-12, 1e-24, 1e-48, 1e-96, 1e-192].into_iter().filter(|h| *h >= smallest)
+        .map(&mut *f).collect::<Result<Vec<f64>, Error>>()?;
+    let [.., a, b, c] = tail.as_slice() else { return Ok(None) };
+    if (a - c).abs().max((b - c).abs()) <= 1e-6 * (1.0 + c.abs()) { return Ok(Some(snap(*c))) }
+    let growing = tail.windows(2).all(|w| w[1].abs() >= 1.5 * w[0].abs() && w[0] * w[1] > 0.0);
+    Ok(growing.then(|| c.signum() * f64::INFINITY))
+}
+fn side(node: &Node, var: &String, a: f64, sign: f64, pos: &Position, file_path: &str, context: &mut Context) -> Result<Option<f64>, Error> {
+    let mut scope = context.scope();
+    let (scale, smallest) = (1.0 + a.abs(), if a.is_finite() { a.abs() * 1e-14 } else { 0.0 });
+    let mut f = |h: f64| -> Result<f64, Error> {
+        let x = if a.is_infinite() { a.signum() / h } else { a + sign * h * scale };
+        at(node, var, x, pos, file_path, &mut scope)
+    };
+    let result = extrapolate(&mut f, smallest);
+    context.rng = scope.rng;
+    result
+}
+
+// -- LIMIT ----------------------------------------------------------------------------
+
+pub fn limit(name: &str, args: &[(Node, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(Error::ArgumentCount(name.to_string(), 3, args.len(), pos.clone(), file_path.to_string()))
+    }
+    let (target_node, target_pos) = &args[0];
+    let mut scope = context.clone();
+    scope.symbolic = true;
+    if let [_, (Node::Variable(var), _), _] = args { scope.stack.retain(|(name, _)| name != var) }
+    let target = interpret((target_node, target_pos), file_path, &mut scope)?;
+    context.rng = scope.rng;
+    let (node, var) = match (&target, args) {
+        (Value::Function(params, body), [_, _]) if params.len() == 1 => (body.clone(), params[0].clone()),
+        (Value::Builtin(builtin), [_, _]) => (apply(builtin, Node::Variable("x".to_string()), target_pos), "x".to_string()),
+        (Value::Poly(p), [_, _]) => (p.node(target_pos), p.var.clone()),
+        (Value::Expr(node), [_, _]) => match free_vars(node).as_slice() {
+            [var] => (node.clone(), var.clone()),
+            vars => return Err(Error::Domain(
+                format!("{name} expects an expression in one variable, got {}", vars.join(" ")), target_pos.clone(), file_path.to_string()
+            ))
+        }
+        (value, [_, (Node::Variable(var), _), _]) => (lift(value, target_pos).ok_or_else(|| Error::IllegalValue(
+            value.clone(), Type::Expr, target_pos.clone(), file_path.to_string()
+        ))?, var.clone()),
+        (_, [_, (node, node_pos), _]) => return Err(Error::ExpectNode(
+            Node::Variable("".to_string()), node.clone(), node_pos.clone(), file_path.to_string()
+        )),
+        (value, _) => return Err(Error::IllegalValue(value.clone(), Type::Builtin(name.to_string()), target_pos.clone(), file_path.to_string()))
+    };
+    let (point_node, point_pos) = args.last().unwrap_or(&args[0]);
+    let point = interpret((point_node, point_pos), file_path, context)?;
+    let a = point.number().filter(|a| !a.is_nan()).ok_or_else(|| Error::IllegalValue(point.clone(), Type::Float, point_pos.clone(), file_path.to_string()))?;
+    if let Some(value) = lhopital(&node, &var, a, pos, file_path, context)? { return Ok(Value::Float(value)) }
+    let fail = || Error::Converge(format!("{name} at {a}"), pos.clone(), file_path.to_string());
+    let sign = match name { "limitl" => -1.0, "limitr" => 1.0, _ => 0.0 };
+    if sign != 0.0 || a.is_infinite() {
+        return side(&node, &var, a, if sign == 0.0 { -a.signum() } else { sign }, pos, file_path, context)?.map(Value::Float).ok_or_else(fail)
+    }
+    let left = side(&node, &var, a, -1.0, pos, file_path, context)?.ok_or_else(fail)?;
+    let right = side(&node, &var, a, 1.0, pos, file_path, context)?.ok_or_else(fail)?;
+    if left == right || left.is_finite() && right.is_finite() && (left - right).abs() <= 1e-6 * (1.0 + left.abs().max(right.abs())) { return Ok(Value::Float((left + right) / 2.0)) }
+    Err(Error::Domain(format!("{name} at {a} differs from the left ({left}) and the right ({right})"), pos.clone(), file_path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::E;
+    use super::*;
+
+    #[test]
+    fn finds_removable_and_infinite_limits() {
+        assert_eq!(eval_lines(&["limit(sin(x)/x x 0)"]).unwrap().to_string(), "1");
+        assert_eq!(eval_lines(&["limit(exp(x) x 0-inf)"]).unwrap().to_string(), "0");
+        assert!((eval_lines(&["limit((1 + 1/n)^n n inf)"]).unwrap().number().unwrap() - E).abs() < 1e-9);
+        assert_eq!(eval_lines(&["limitl(abs(x)/x x 0)"]).unwrap().to_string(), "-1");
+    }
+    #[test]
+    fn rejects_oscillating_and_two_sided_limits() {
+        assert!(matches!(eval_lines(&["limit(sin(1/x) x 0)"]), Err(Error::Converge(_, _, _))));
+        assert!(eval_lines(&["limit(1/x x 0)"]).unwrap_err().to_string().starts_with("ERROR: limit at 0 differs"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,50 @@
+// -- LINEAR ALGEBRA -------------------------------------------------------------------
+
+#[allow(clippy::needless_range_loop)]
+pub fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 { return None }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = a[row][col] / a[col][col];
+            for k in col..n { a[row][k] -= factor * a[col][k] }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+#[allow(clippy::needless_range_loop)]
+pub fn least_squares(a: &[Vec<f64>], b: &[f64]) -> Option<Vec<f64>> {
+    let (rows, cols) = (a.len(), a.first()?.len());
+    let mut r: Vec<Vec<f64>> = a.to_vec();
+    let mut qtb: Vec<f64> = b.to_vec();
+    for col in 0..cols {
+        let norm = (col..rows).map(|i| r[i][col].powi(2)).sum::<f64>().sqrt();
+        if norm < 1e-12 { return None }
+        let alpha = if r[col][col] > 0.0 { -norm } else { norm };
+        let mut v: Vec<f64> = (col..rows).map(|i| r[i][col]).collect();
+        v[0] -= alpha;
+        let v_norm = v.iter().map(|x| x * x).sum::<f64>();
+        if v_norm == 0.0 { continue }
+        for k in col..cols {
+            let dot: f64 = (col..rows).map(|i| v[i - col] * r[i][k]).sum();
+            for i in col..rows { r[i][k] -= 2.0 * v[i - col] * dot / v_norm }
+        }
+        let dot: f64 = (col..rows).map(|i| v[i - col] * qtb[i]).sum();
+        for i in col..rows { qtb[i] -= 2.0 * v[i - col] * dot / v_norm }
+    }
+    let mut x = vec![0.0; cols];
+    for row in (0..cols).rev() {
+        let sum: f64 = (row + 1..cols).map(|k| r[row][k] * x[k]).sum();
+        x[row] = (qtb[row] - sum) / r[row][row];
+    }
+    Some(x)
+}
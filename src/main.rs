@@ -1,6 +1,8 @@
 // #![allow(dead_code)]
 // #![allow(unused_variables)]
 // #![allow(unused_must_use)]
+#![allow(clippy::result_large_err)]
+#![allow(clippy::upper_case_acronyms)]
 
 extern crate logos;
 use std::io::Write;
@@ -11,17 +13,56 @@ mod position;
 mod lexer;
 mod parser;
 mod interpreter;
+mod builtins;
+mod number;
+mod vector;
+mod functional;
+mod stats;
+mod linalg;
+mod fit;
+mod random;
+mod probability;
+mod elementary;
+mod symbolic;
+mod roots;
+mod complex;
+mod polynomial;
+mod quadrature;
+mod ode;
+mod optimize;
+mod limit;
+mod dual;
+mod signal;
 use crate::interpreter::*;
 
 // -- INTERPRET ------------------------------------------------------------------------
 
 fn main() {
-    let args: Vec<String> = env::args().collect::<Vec<String>>();
-    if args.len() > 1 {
-        runfile(args[1].as_str());
+    let mut args = env::args().skip(1);
+    let mut seed: Option<u64> = None;
+    let mut path: Option<String> = None;
+    let mut symbolic = false;
+    while let Some(arg) = args.next() {
+        if arg == "--symbolic" {
+            symbolic = true;
+        } else if arg == "--seed" {
+            match args.next().and_then(|s| s.parse::<u64>().ok()) {
+                Some(s) => seed = Some(s),
+                None => { println!("--seed expects a non-negative integer"); return }
+            }
+        } else {
+            path = Some(arg);
+        }
+    }
+    let mut context = match seed {
+        Some(seed) => Context::seeded(seed),
+        None => Context::new()
+    };
+    context.symbolic = symbolic;
+    if let Some(path) = path {
+        runfile(path.as_str(), &mut context);
         return
     }
-    let mut context = Context::new();
     loop {
         let mut input = String::new();
         print!("> ");
@@ -0,0 +1,289 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::polynomial::*;
+
+// -- ALGORITHMS -----------------------------------------------------------------------
+
+fn gcd_u64(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 { (a, b) = (b, a % b) }
+    a
+}
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+fn pow_mod(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut result = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 { result = mul_mod(result, base, m) }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+fn inv_mod(a: i64, m: i64) -> Option<i64> {
+    let (mut old_r, mut r) = (a.rem_euclid(m) as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    if old_r != 1 { return None }
+    Some(old_s.rem_euclid(m as i128) as i64)
+}
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 { return false }
+    for p in [2u64, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        if n.is_multiple_of(p) { return n == p }
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d % 2 == 0 { d /= 2; s += 1 }
+    'witness: for a in [2u64, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 { continue }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 { continue 'witness }
+        }
+        return false
+    }
+    true
+}
+fn pollard_rho(n: u64) -> u64 {
+    if n.is_multiple_of(2) { return 2 }
+    let mut c = 1;
+    loop {
+        let f = |x: u64| (mul_mod(x, x, n) + c) % n;
+        let (mut x, mut y, mut d) = (2, 2, 1);
+        while d == 1 {
+            x = f(x);
+            y = f(f(y));
+            d = gcd_u64(x.abs_diff(y), n);
+        }
+        if d != n { return d }
+        c += 1;
+    }
+}
+fn prime_factors(n: u64, factors: &mut Vec<u64>) {
+    if n == 1 { return }
+    if is_prime(n) { factors.push(n); return }
+    for p in [2u64, 3, 5, 7, 11, 13] {
+        if n.is_multiple_of(p) {
+            factors.push(p);
+            prime_factors(n / p, factors);
+            return
+        }
+    }
+    let d = pollard_rho(n);
+    prime_factors(d, factors);
+    prime_factors(n / d, factors);
+}
+pub fn factorize(n: u64) -> Vec<u64> {
+    let mut factors: Vec<u64> = vec![];
+    prime_factors(n, &mut factors);
+    factors.sort();
+    factors
+}
+pub fn factorial(n: i64) -> Option<i64> {
+    let mut result: i64 = 1;
+    for i in 2..=n {
+        result = result.checked_mul(i)?;
+    }
+    Some(result)
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn domain(detail: String, pos: &Position, file_path: &str) -> Error {
+    Error::Domain(detail, pos.clone(), file_path.to_string())
+}
+fn overflow(name: &str, pos: &Position, file_path: &str) -> Error {
+    Error::Overflow(name.to_string(), pos.clone(), file_path.to_string())
+}
+fn expect_positive(name: &str, arg: &(Value, Position), file_path: &str) -> Result<i64, Error> {
+    let int = expect_int(name, arg, file_path)?;
+    if int < 1 { return Err(domain(format!("{name} expects a positive int, got {int}"), &arg.1, file_path)) }
+    Ok(int)
+}
+
+pub fn gcd(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("gcd", args, 2, pos, file_path)?;
+    if let (Value::Poly(p), _) | (_, Value::Poly(p)) = (&args[0].0, &args[1].0) {
+        let as_poly = |value: &Value| match value {
+            Value::Poly(q) => Some(q.clone()),
+            value => value.number().map(|c| Polynomial::new(vec![c], &p.var))
+        };
+        let (a, b) = (as_poly(&args[0].0), as_poly(&args[1].0));
+        let (Some(a), Some(b)) = (a, b) else { return Err(illegal_arg("gcd", &args[0], file_path)) };
+        return Ok(Value::Poly(a.gcd(&b)))
+    }
+    let a = expect_int("gcd", &args[0], file_path)?;
+    let b = expect_int("gcd", &args[1], file_path)?;
+    let g = gcd_u64(a.unsigned_abs(), b.unsigned_abs());
+    i64::try_from(g).map(Value::Int).map_err(|_| overflow("gcd", pos, file_path))
+}
+pub fn lcm(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("lcm", args, 2, pos, file_path)?;
+    let a = expect_int("lcm", &args[0], file_path)?;
+    let b = expect_int("lcm", &args[1], file_path)?;
+    if a == 0 || b == 0 { return Ok(Value::Int(0)) }
+    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+    (a / gcd_u64(a, b)).checked_mul(b)
+    .and_then(|l| i64::try_from(l).ok())
+    .map(Value::Int).ok_or_else(|| overflow("lcm", pos, file_path))
+}
+pub fn isprime(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("isprime", args, 1, pos, file_path)?;
+    map_int("isprime", &args[0], file_path, &|n| {
+        Ok(Value::Bool(n > 0 && is_prime(n as u64)))
+    })
+}
+pub fn factor(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("factor", args, 1, pos, file_path)?;
+    if let Value::Poly(p) = &args[0].0 { return poly_factor(p, &args[0].1, file_path) }
+    let n = expect_positive("factor", &args[0], file_path)?;
+    Ok(Value::Vector(factorize(n as u64).into_iter().map(|p| Value::Int(p as i64)).collect()))
+}
+pub fn nextprime(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("nextprime", args, 1, pos, file_path)?;
+    map_int("nextprime", &args[0], file_path, &|n| {
+        let mut p = if n < 2 { 2 } else { n.checked_add(1).ok_or_else(|| overflow("nextprime", pos, file_path))? };
+        while !is_prime(p as u64) {
+            p = p.checked_add(1).ok_or_else(|| overflow("nextprime", pos, file_path))?;
+        }
+        Ok(Value::Int(p))
+    })
+}
+pub fn modpow(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("modpow", args, 3, pos, file_path)?;
+    let base = expect_int("modpow", &args[0], file_path)?;
+    let exp = expect_int("modpow", &args[1], file_path)?;
+    let m = expect_positive("modpow", &args[2], file_path)?;
+    let base = if exp < 0 {
+        inv_mod(base, m).ok_or_else(|| domain(format!("{base} has no inverse modulo {m}"), &args[0].1, file_path))?
+    } else { base.rem_euclid(m) };
+    Ok(Value::Int(pow_mod(base as u64, exp.unsigned_abs(), m as u64) as i64))
+}
+pub fn modinv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("modinv", args, 2, pos, file_path)?;
+    let a = expect_int("modinv", &args[0], file_path)?;
+    let m = expect_positive("modinv", &args[1], file_path)?;
+    inv_mod(a, m).map(Value::Int)
+    .ok_or_else(|| domain(format!("{a} has no inverse modulo {m}"), &args[0].1, file_path))
+}
+pub fn totient(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("totient", args, 1, pos, file_path)?;
+    map_int("totient", &args[0], file_path, &|n| {
+        if n < 1 { return Err(domain(format!("totient expects a positive int, got {n}"), &args[0].1, file_path)) }
+        let mut factors = factorize(n as u64);
+        factors.dedup();
+        let mut result = n as u64;
+        for p in factors { result = result / p * (p - 1) }
+        Ok(Value::Int(result as i64))
+    })
+}
+pub fn divisors(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("divisors", args, 1, pos, file_path)?;
+    let n = expect_positive("divisors", &args[0], file_path)?;
+    let mut divisors: Vec<u64> = vec![1];
+    let factors = factorize(n as u64);
+    let mut i = 0;
+    while i < factors.len() {
+        let p = factors[i];
+        let mut count = 0;
+        while i < factors.len() && factors[i] == p { count += 1; i += 1 }
+        let len = divisors.len();
+        let mut power = 1;
+        for _ in 0..count {
+            power *= p;
+            for j in 0..len { divisors.push(divisors[j] * power) }
+        }
+    }
+    divisors.sort();
+    Ok(Value::Vector(divisors.into_iter().map(|d| Value::Int(d as i64)).collect()))
+}
+pub fn binom(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("binom", args, 2, pos, file_path)?;
+    let n = expect_int("binom", &args[0], file_path)?;
+    let k = expect_int("binom", &args[1], file_path)?;
+    if n < 0 { return Err(domain(format!("binom expects a non-negative n, got {n}"), &args[0].1, file_path)) }
+    if k < 0 || k > n { return Ok(Value::Int(0)) }
+    let k = k.min(n - k) as u128;
+    let mut result: u128 = 1;
+    for i in 0..k {
+        result = result * (n as u128 - i) / (i + 1);
+        if result > i64::MAX as u128 { return Err(overflow("binom", pos, file_path)) }
+    }
+    Ok(Value::Int(result as i64))
+}
+pub fn perm(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("perm", args, 2, pos, file_path)?;
+    let n = expect_int("perm", &args[0], file_path)?;
+    let k = expect_int("perm", &args[1], file_path)?;
+    if n < 0 { return Err(domain(format!("perm expects a non-negative n, got {n}"), &args[0].1, file_path)) }
+    if k < 0 || k > n { return Ok(Value::Int(0)) }
+    let mut result: i64 = 1;
+    for i in n - k + 1..=n {
+        result = result.checked_mul(i).ok_or_else(|| overflow("perm", pos, file_path))?;
+    }
+    Ok(Value::Int(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shown(line: &str) -> String {
+        match eval_lines(&[line]) {
+            Ok(value) => value.to_string(),
+            Err(error) => error.to_string()
+        }
+    }
+
+    #[test]
+    fn gcd_and_lcm_ignore_signs_and_catch_overflow() {
+        assert_eq!(gcd_u64(12, 18), 6);
+        assert_eq!(shown("gcd(12 (0-18))"), "6");
+        assert_eq!(shown("lcm(4 6)"), "12");
+        assert_eq!(shown("lcm(0 5)"), "0");
+        assert!(shown("lcm(9223372036854775807 2)").starts_with("ERROR: integer overflow in lcm"));
+    }
+    #[test]
+    fn primality_is_exact_past_trial_division() {
+        assert!(!is_prime(1) && is_prime(2) && !is_prime(561));
+        assert!(is_prime(1000000007) && is_prime(18446744073709551557));
+        assert!(!is_prime(3215031751));
+        assert!(!is_prime(4294967291 * 4294967279));
+    }
+    #[test]
+    fn pollard_rho_splits_semiprimes() {
+        assert_eq!(factorize(998244359987710471), vec![998244353, 1000000007]);
+        assert_eq!(factorize(4294967291 * 4294967279), vec![4294967279, 4294967291]);
+        assert_eq!(factorize(360), vec![2, 2, 2, 3, 3, 5]);
+        assert_eq!(factorize(1), Vec::<u64>::new());
+    }
+    #[test]
+    fn factorials_and_counts_overflow_to_errors() {
+        assert_eq!(factorial(20), Some(2432902008176640000));
+        assert_eq!(factorial(21), None);
+        assert!(shown("21!").starts_with("ERROR: integer overflow in '!'"));
+        assert_eq!(shown("binom(5 2)"), "10");
+        assert_eq!(shown("binom(5 7)"), "0");
+        assert_eq!(shown("binom(66 33)"), "7219428434016265740");
+        assert!(shown("binom(67 33)").starts_with("ERROR: integer overflow in binom"));
+        assert_eq!(shown("perm(5 2)"), "20");
+        assert!(shown("perm(30 25)").starts_with("ERROR: integer overflow in perm"));
+    }
+    #[test]
+    fn modular_inverses_and_divisor_functions() {
+        assert_eq!(shown("modinv(3 7)"), "5");
+        assert!(shown("modinv(2 4)").starts_with("ERROR: 2 has no inverse modulo 4"));
+        assert_eq!(shown("modpow(3 (0-1) 7)"), "5");
+        assert_eq!(shown("totient([1 36 97])"), "[1, 12, 96]");
+        assert_eq!(shown("divisors(12)"), "[1, 2, 3, 4, 6, 12]");
+        assert_eq!(shown("divisors(1)"), "[1]");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,235 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::symbolic::*;
+use crate::polynomial::*;
+
+const DORMAND_PRINCE: [(f64, &[f64]); 7] = [
+    (0.0, &[]),
+    (1.0 / 5.0, &[1.0 / 5.0]),
+    (3.0 / 10.0, &[3.0 / 40.0, 9.0 / 40.0]),
+    (4.0 / 5.0, &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0]),
+    (8.0 / 9.0, &[19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0]),
+    (1.0, &[9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0]),
+    (1.0, &[35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0])
+];
+const FOURTH_ORDER: [f64; 7] = [5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0, 187.0 / 2100.0, 1.0 / 40.0];
+
+struct System<'a> {
+    func: &'a Value,
+    event: Option<&'a Value>,
+    scalar: bool,
+    pos: &'a Position,
+    file_path: &'a str,
+    context: &'a mut Context
+}
+impl System<'_> {
+    fn state(&self, y: &[f64]) -> Value {
+        if self.scalar { Value::Float(y[0]) } else { Value::Vector(y.iter().map(|y| Value::Float(*y)).collect()) }
+    }
+    fn numbers(&self, value: Value, len: usize) -> Result<Vec<f64>, Error> {
+        let illegal = |value: Value| Error::IllegalValue(value, Type::Builtin("ode".to_string()), self.pos.clone(), self.file_path.to_string());
+        let numbers = match &value {
+            Value::Vector(values) if !self.scalar => values.iter().map(|v| v.number()).collect::<Option<Vec<f64>>>(),
+            value if self.scalar => value.number().map(|y| vec![y]),
+            _ => None
+        }.ok_or_else(|| illegal(value.clone()))?;
+        if numbers.len() != len { return Err(Error::Shape(len, numbers.len(), self.pos.clone(), self.file_path.to_string())) }
+        Ok(numbers)
+    }
+    fn call(&mut self, func: &Value, t: f64, y: &[f64]) -> Result<Value, Error> {
+        let args = [(Value::Float(t), self.pos.clone()), (self.state(y), self.pos.clone())];
+        call(func, None, &args, self.pos, self.file_path, self.context)
+    }
+    fn slope(&mut self, t: f64, y: &[f64]) -> Result<Vec<f64>, Error> {
+        let value = self.call(self.func, t, y)?;
+        self.numbers(value, y.len())
+    }
+    fn event(&mut self, t: f64, y: &[f64]) -> Result<Option<f64>, Error> {
+        let Some(event) = self.event else { return Ok(None) };
+        let value = match event {
+            Value::Poly(p) => poly_eval(p, &(Value::Float(t), self.pos.clone()), self.file_path)?,
+            event => self.call(event, t, y)?
+        };
+        value.number().map(Some).ok_or_else(|| Error::IllegalValue(value, Type::Builtin("ode".to_string()), self.pos.clone(), self.file_path.to_string()))
+    }
+    fn rk4(&mut self, t: f64, y: &[f64], h: f64) -> Result<(Vec<f64>, f64), Error> {
+        let shift = |k: &[f64], scale: f64| -> Vec<f64> { y.iter().zip(k).map(|(y, k)| y + scale * k).collect() };
+        let k1 = self.slope(t, y)?;
+        let k2 = self.slope(t + h / 2.0, &shift(&k1, h / 2.0))?;
+        let k3 = self.slope(t + h / 2.0, &shift(&k2, h / 2.0))?;
+        let k4 = self.slope(t + h, &shift(&k3, h))?;
+        Ok(((0..y.len()).map(|i| y[i] + h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i])).collect(), 0.0))
+    }
+    fn dormand_prince(&mut self, t: f64, y: &[f64], h: f64) -> Result<(Vec<f64>, f64), Error> {
+        let mut stages: Vec<Vec<f64>> = vec![];
+        for (c, weights) in DORMAND_PRINCE {
+            let point: Vec<f64> = (0..y.len()).map(|i| y[i] + h * weights.iter().zip(&stages).map(|(w, k)| w * k[i]).sum::<f64>()).collect();
+            stages.push(self.slope(t + c * h, &point)?);
+        }
+        let (_, fifth) = DORMAND_PRINCE[6];
+        let next: Vec<f64> = (0..y.len()).map(|i| y[i] + h * fifth.iter().zip(&stages).map(|(w, k)| w * k[i]).sum::<f64>()).collect();
+        let error = (0..y.len()).map(|i| {
+            let estimate = h * (0..7).map(|s| (fifth.get(s).unwrap_or(&0.0) - FOURTH_ORDER[s]) * stages[s][i]).sum::<f64>();
+            estimate.abs()
+        }).collect::<Vec<f64>>();
+        let scaled = error.iter().enumerate().map(|(i, e)| e / (1.0 + y[i].abs().max(next[i].abs()))).fold(0.0, f64::max);
+        Ok((next, scaled))
+    }
+    fn step(&mut self, t: f64, y: &[f64], h: f64, fixed: bool) -> Result<(Vec<f64>, f64), Error> {
+        if fixed { self.rk4(t, y, h) } else { self.dormand_prince(t, y, h) }
+    }
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+pub fn ode(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    if args.len() < 4 || args.len() > 6 {
+        return Err(Error::ArgumentCount("ode".to_string(), 4, args.len(), pos.clone(), file_path.to_string()))
+    }
+    let func = expect_callable("ode", &args[0], file_path)?;
+    let scalar = !matches!(args[1].0, Value::Vector(_));
+    let mut y: Vec<f64> = match &args[1].0 {
+        Value::Vector(values) => values.iter().map(|v| v.number()).collect::<Option<Vec<f64>>>(),
+        value => value.number().map(|y| vec![y])
+    }.ok_or_else(|| illegal_arg("ode", &args[1], file_path))?;
+    let t0 = expect_float("ode", &args[2], file_path)?;
+    let t1 = expect_float("ode", &args[3], file_path)?;
+    for (t, arg) in [(t0, &args[2]), (t1, &args[3])] {
+        if !t.is_finite() { return Err(illegal_arg("ode", arg, file_path)) }
+    }
+    // options come in any order and are told apart by type, an int is a number of fixed steps,
+    // a float is the tolerance and a function, expression in t and y or polynomial in t is an event
+    let (mut steps, mut tol, mut event): (Option<i64>, Option<f64>, Option<Value>) = (None, None, None);
+    for arg in &args[4..] {
+        let domain = |detail: String| Error::Domain(detail, arg.1.clone(), file_path.to_string());
+        match &arg.0 {
+            Value::Int(n) if *n < 1 => return Err(domain(format!("ode expects a positive number of steps, got {n}"))),
+            Value::Float(float) if !float.is_finite() || *float <= 0.0 => return Err(domain(format!("ode expects a positive tolerance, got {float}"))),
+            Value::Int(_) if steps.is_some() => return Err(domain("ode got more than one number of steps".to_string())),
+            Value::Float(_) if tol.is_some() => return Err(domain("ode got more than one tolerance".to_string())),
+            Value::Int(n) => steps = Some(*n),
+            Value::Float(float) => tol = Some(*float),
+            _ if event.is_some() => return Err(domain("ode got more than one event".to_string())),
+            Value::Function(_, _) | Value::Builtin(_) | Value::Poly(_) => event = Some(arg.0.clone()),
+            Value::Expr(node) if free_vars(node).iter().all(|var| var == "t" || var == "y") =>
+                event = Some(Value::Function(vec!["t".to_string(), "y".to_string()], node.clone())),
+            _ => return Err(illegal_arg("ode", arg, file_path))
+        }
+    }
+    let tol = tol.unwrap_or(1e-8);
+    let mut system = System { func: &func, event: event.as_ref(), scalar, pos, file_path, context };
+    let (mut t, mut ts, mut ys) = (t0, vec![t0], vec![y.clone()]);
+    let mut h = (t1 - t0) / steps.unwrap_or(100) as f64;
+    let mut g = system.event(t, &y)?;
+    let mut count = 0;
+    while (t1 - t) * h.signum() > 0.0 {
+        count += 1;
+        if count > steps.unwrap_or(0).max(100000) { return Err(Error::Converge(format!("ode from {t0} to {t1}"), pos.clone(), file_path.to_string())) }
+        match steps {
+            Some(n) => h = t0 + (t1 - t0) * count as f64 / n as f64 - t,
+            None => if (t + h - t1) * h.signum() > 0.0 { h = t1 - t }
+        }
+        let (next, error) = system.step(t, &y, h, steps.is_some())?;
+        let finite = error.is_finite() && next.iter().all(|y| y.is_finite());
+        if !finite && steps.is_some() { return Err(Error::Converge(format!("ode at t = {t}"), pos.clone(), file_path.to_string())) }
+        if !finite || error > tol {
+            h *= if finite { (0.9 * (tol / error).powf(0.2)).max(0.2) } else { 0.2 };
+            if h.abs() <= 1e-14 * (1.0 + t.abs()) { return Err(Error::Converge(format!("ode at t = {t}"), pos.clone(), file_path.to_string())) }
+            continue
+        }
+        let g_next = system.event(t + h, &next)?;
+        if let (Some(g_prev), Some(g_next)) = (g, g_next) {
+            if g_prev != 0.0 && g_prev * g_next <= 0.0 {
+                let (mut lo, mut hi) = (0.0, h);
+                let mut hit = next.clone();
+                for _ in 0..60 {
+                    let mid = (lo + hi) / 2.0;
+                    let (point, _) = system.step(t, &y, mid, steps.is_some())?;
+                    if g_prev * system.event(t + mid, &point)?.unwrap_or(0.0) > 0.0 { lo = mid } else { (hi, hit) = (mid, point) }
+                }
+                ts.push(t + hi);
+                ys.push(hit);
+                break
+            }
+        }
+        (t, y, g) = (t + h, next, g_next);
+        ts.push(t);
+        ys.push(y.clone());
+        if steps.is_none() { h *= (0.9 * (tol / error.max(1e-300)).powf(0.2)).min(5.0) }
+    }
+    Ok(Value::Vector(vec![
+        Value::Vector(ts.into_iter().map(Value::Float).collect()),
+        Value::Vector(ys.iter().map(|y| system.state(y)).collect())
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_state(lines: &[&str]) -> Vec<f64> {
+        let Value::Vector(result) = eval_lines(lines).unwrap() else { panic!("expected times and states") };
+        match &result[1] {
+            Value::Vector(states) => match states.last().unwrap() {
+                Value::Vector(y) => y.iter().map(|y| y.number().unwrap()).collect(),
+                y => vec![y.number().unwrap()]
+            }
+            value => panic!("expected states, got {value}")
+        }
+    }
+
+    #[test]
+    fn adaptive_and_fixed_steps_follow_decay() {
+        assert!((last_state(&["f(t y) : 0 - y", "ode(f 1 0 1)"])[0] - (-1f64).exp()).abs() < 1e-8);
+        assert!((last_state(&["f(t y) : 0 - y", "ode(f 1 0 1 100)"])[0] - (-1f64).exp()).abs() < 1e-9);
+        assert!((last_state(&["f(t y) : 0 - y", "ode(f 1 1 0)"])[0] - 1f64.exp()).abs() < 1e-8);
+    }
+    #[test]
+    fn systems_keep_their_shape() {
+        let y = last_state(&["o(t y) : [y # 1  0-y # 0]", "ode(o [1 0] 0 2*pi)"]);
+        assert!((y[0] - 1.0).abs() < 1e-6 && y[1].abs() < 1e-6);
+        assert!(eval_lines(&["o(t y) : [y # 1]", "ode(o [1 0] 0 1)"]).is_err());
+    }
+    #[test]
+    fn events_stop_at_the_crossing() {
+        let Value::Vector(result) = eval_lines(&["fall(t y) : [y # 1  0-9.81]", "ground(t y) : y # 0", "ode(fall [10 0] 0 5 ground)"]).unwrap()
+        else { panic!("expected times and states") };
+        let Value::Vector(ts) = &result[0] else { panic!("expected times") };
+        assert!((ts.last().unwrap().number().unwrap() - (20.0 / 9.81f64).sqrt()).abs() < 1e-9);
+    }
+    #[test]
+    fn options_are_told_apart_by_type() {
+        let times = |line: &str| {
+            let Value::Vector(result) = eval_lines(&["f(t y) : 0 - y", "symbolic(1)", line]).unwrap() else { panic!("expected times and states") };
+            let Value::Vector(ts) = &result[0] else { panic!("expected times") };
+            ts.iter().map(|t| t.number().unwrap()).collect::<Vec<f64>>()
+        };
+        assert_eq!(times("ode(f 1 0 1 4)").len(), 5);
+        assert_eq!(times("ode(f 1 0 1 0.5 4)"), times("ode(f 1 0 1 4 0.5)"));
+        assert!((times("ode(f 1 0 5 y - 0.5)").last().unwrap() - 2f64.ln()).abs() < 1e-8);
+        assert!((times("ode(f 1 0 5 poly([1 (0-1)]))").last().unwrap() - 1.0).abs() < 1e-12);
+        for (line, message) in [
+            ("ode(f 1 0 1 0)", "ode expects a positive number of steps, got 0"),
+            ("ode(f 1 0 1 4 5)", "ode got more than one number of steps"),
+            ("ode(f 1 0 1 0.1 0.2)", "ode got more than one tolerance"),
+            ("ode(f 1 0 1 f f)", "ode got more than one event")
+        ] {
+            assert!(eval_lines(&["f(t y) : 0 - y", line]).unwrap_err().to_string().starts_with(&format!("ERROR: {message}")), "{line}");
+        }
+    }
+    #[test]
+    fn infinite_times_blame_their_own_argument() {
+        let Err(Error::IllegalValue(_, _, pos, _)) = eval_lines(&["f(t y) : 0 - y", "ode(f 1 inf 1)"]) else { panic!("expected an illegal t0") };
+        let Err(Error::IllegalValue(_, _, end_pos, _)) = eval_lines(&["f(t y) : 0 - y", "ode(f 1 0 inf)"]) else { panic!("expected an illegal t1") };
+        assert!(pos != end_pos);
+    }
+    #[test]
+    fn blow_ups_do_not_converge() {
+        for line in ["ode(b 1 0 2)", "ode(b 1 0 2 100)"] {
+            let error = eval_lines(&["b(t y) : y^2", line]).unwrap_err();
+            assert!(matches!(error, Error::Converge(_, _, _)), "{error}");
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,322 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+
+struct Objective<'a> {
+    func: &'a Value,
+    spread: bool,
+    scalar: bool,
+    sign: f64,
+    bounds: Option<(Vec<f64>, Vec<f64>)>,
+    evaluations: i64,
+    pos: &'a Position,
+    file_path: &'a str,
+    context: &'a mut Context
+}
+impl Objective<'_> {
+    fn point(&self, x: &[f64]) -> Value {
+        if self.scalar { Value::Float(x[0]) } else { Value::Vector(x.iter().map(|x| Value::Float(*x)).collect()) }
+    }
+    fn inside(&self, u: &[f64]) -> Vec<f64> {
+        let Some((lo, hi)) = &self.bounds else { return u.to_vec() };
+        u.iter().enumerate().map(|(i, u)| match (lo[i].is_finite(), hi[i].is_finite()) {
+            (true, true) => lo[i] + (hi[i] - lo[i]) * (u.sin() + 1.0) / 2.0,
+            (true, false) => lo[i] + u * u,
+            (false, true) => hi[i] - u * u,
+            (false, false) => *u
+        }).collect()
+    }
+    fn outside(&self, x: &[f64]) -> Vec<f64> {
+        let Some((lo, hi)) = &self.bounds else { return x.to_vec() };
+        x.iter().enumerate().map(|(i, x)| match (lo[i].is_finite(), hi[i].is_finite()) {
+            (true, true) => (2.0 * (x - lo[i]) / (hi[i] - lo[i]) - 1.0).clamp(-0.999, 0.999).asin(),
+            (true, false) => (x - lo[i]).max(1e-4).sqrt(),
+            (false, true) => (hi[i] - x).max(1e-4).sqrt(),
+            (false, false) => *x
+        }).collect()
+    }
+    fn eval(&mut self, u: &[f64]) -> Result<f64, Error> {
+        self.evaluations += 1;
+        let x = self.inside(u);
+        let args: Vec<(Value, Position)> = if self.spread {
+            x.iter().map(|x| (Value::Float(*x), self.pos.clone())).collect()
+        } else {
+            vec![(self.point(&x), self.pos.clone())]
+        };
+        match call(self.func, None, &args, self.pos, self.file_path, self.context) {
+            Ok(value) => {
+                let y = value.number().ok_or_else(|| Error::IllegalValue(
+                    value, Type::Builtin("minimize".to_string()), self.pos.clone(), self.file_path.to_string()
+                ))?;
+                Ok(if y.is_nan() { f64::INFINITY } else { self.sign * y })
+            }
+            Err(Error::Domain(_, _, _)) => Ok(f64::INFINITY),
+            Err(error) => Err(error)
+        }
+    }
+    fn gradient(&mut self, x: &[f64]) -> Result<Vec<f64>, Error> {
+        let mut gradient = vec![0.0; x.len()];
+        for i in 0..x.len() {
+            let mut h = 1e-6 * (1.0 + x[i].abs());
+            // widen the step until the difference rises above the rounding noise of large function values
+            loop {
+                let (mut up, mut down) = (x.to_vec(), x.to_vec());
+                up[i] += h;
+                down[i] -= h;
+                let (f_up, f_down) = (self.eval(&up)?, self.eval(&down)?);
+                gradient[i] = (f_up - f_down) / (2.0 * h);
+                if (f_up - f_down).abs() > 1e-13 * (f_up.abs() + f_down.abs()) || h >= 0.1 * (1.0 + x[i].abs()) { break }
+                h *= 10.0;
+            }
+        }
+        Ok(gradient)
+    }
+}
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+fn converged(old: f64, new: f64) -> bool {
+    (old - new).abs() <= 1e-12 * (1.0 + new.abs())
+}
+
+// -- METHODS --------------------------------------------------------------------------
+
+fn bfgs(f: &mut Objective, x0: &[f64]) -> Result<Option<(Vec<f64>, f64, i64)>, Error> {
+    let n = x0.len();
+    let identity = || (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect::<Vec<Vec<f64>>>();
+    let (mut x, mut fx) = (x0.to_vec(), f.eval(x0)?);
+    let mut g = f.gradient(&x)?;
+    let mut h = identity();
+    for iteration in 1..=200 * n as i64 {
+        if !fx.is_finite() || g.iter().any(|g| !g.is_finite()) { return Ok(None) }
+        if g.iter().zip(&x).all(|(g, x)| g.abs() <= 1e-9 * (1.0 + x.abs())) { return Ok(Some((x, fx, iteration))) }
+        let mut p: Vec<f64> = h.iter().map(|row| -dot(row, &g)).collect();
+        if dot(&g, &p) >= 0.0 {
+            h = identity();
+            p = g.iter().map(|g| -g).collect();
+        }
+        let slope = dot(&g, &p);
+        let mut step = 1.0;
+        let (next, f_next) = loop {
+            let next: Vec<f64> = x.iter().zip(&p).map(|(x, p)| x + step * p).collect();
+            let f_next = f.eval(&next)?;
+            if f_next <= fx + 1e-4 * step * slope { break (next, f_next) }
+            step /= 2.0;
+            if step < 1e-12 { return Ok(Some((x, fx, iteration))) }
+        };
+        let g_next = f.gradient(&next)?;
+        let s: Vec<f64> = next.iter().zip(&x).map(|(a, b)| a - b).collect();
+        let y: Vec<f64> = g_next.iter().zip(&g).map(|(a, b)| a - b).collect();
+        let sy = dot(&s, &y);
+        if sy > 1e-12 * dot(&s, &s).sqrt() * dot(&y, &y).sqrt() {
+            let hy: Vec<f64> = h.iter().map(|row| dot(row, &y)).collect();
+            let yhy = dot(&y, &hy);
+            for i in 0..n {
+                for j in 0..n {
+                    h[i][j] += ((sy + yhy) * s[i] * s[j]) / (sy * sy) - (hy[i] * s[j] + s[i] * hy[j]) / sy;
+                }
+            }
+        }
+        let done = converged(fx, f_next) && s.iter().zip(&next).all(|(s, x)| s.abs() <= 1e-9 * (1.0 + x.abs()));
+        (x, fx, g) = (next, f_next, g_next);
+        if done { return Ok(Some((x, fx, iteration))) }
+    }
+    Ok(None)
+}
+// a result is only a minimum if no nearby point along an axis is clearly lower, which rejects unbounded descents
+fn minimum(f: &mut Objective, x: &[f64], fx: f64) -> Result<bool, Error> {
+    if !fx.is_finite() { return Ok(false) }
+    for i in 0..x.len() {
+        for sign in [1.0, -1.0] {
+            let mut probe = x.to_vec();
+            probe[i] += sign * 1e-4 * (1.0 + x[i].abs());
+            if f.eval(&probe)? < fx - 1e-9 * (1.0 + fx.abs()) { return Ok(false) }
+        }
+    }
+    Ok(true)
+}
+fn nelder_mead(f: &mut Objective, x0: &[f64]) -> Result<Option<(Vec<f64>, f64, i64)>, Error> {
+    let n = x0.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = vec![(x0.to_vec(), f.eval(x0)?)];
+    for i in 0..n {
+        let mut vertex = x0.to_vec();
+        vertex[i] += if vertex[i] == 0.0 { 0.00025 } else { 0.05 * vertex[i] };
+        let value = f.eval(&vertex)?;
+        simplex.push((vertex, value));
+    }
+    for iteration in 1..=1000 * n as i64 {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (best, worst) = (simplex[0].clone(), simplex[n].clone());
+        let size = simplex.iter().flat_map(|(v, _)| v.iter().zip(&best.0).map(|(a, b)| (a - b).abs())).fold(0.0, f64::max);
+        if best.1.is_finite() && converged(best.1, worst.1) && size <= 1e-9 * (1.0 + best.0.iter().fold(0.0_f64, |acc, x| acc.max(x.abs()))) {
+            return Ok(Some((best.0, best.1, iteration)))
+        }
+        let centroid: Vec<f64> = (0..n).map(|i| simplex[..n].iter().map(|(v, _)| v[i]).sum::<f64>() / n as f64).collect();
+        let along = |t: f64| -> Vec<f64> { centroid.iter().zip(&worst.0).map(|(c, w)| c + t * (c - w)).collect() };
+        let reflected = along(1.0);
+        let f_reflected = f.eval(&reflected)?;
+        if f_reflected < best.1 {
+            let expanded = along(2.0);
+            let f_expanded = f.eval(&expanded)?;
+            simplex[n] = if f_expanded < f_reflected { (expanded, f_expanded) } else { (reflected, f_reflected) };
+            continue
+        }
+        if f_reflected < simplex[n - 1].1 { simplex[n] = (reflected, f_reflected); continue }
+        let contracted = along(if f_reflected < worst.1 { 0.5 } else { -0.5 });
+        let f_contracted = f.eval(&contracted)?;
+        if f_contracted < worst.1.min(f_reflected) { simplex[n] = (contracted, f_contracted); continue }
+        for vertex in simplex.iter_mut().skip(1) {
+            let shrunk: Vec<f64> = vertex.0.iter().zip(&best.0).map(|(v, b)| b + 0.5 * (v - b)).collect();
+            *vertex = (shrunk.clone(), f.eval(&shrunk)?);
+        }
+    }
+    Ok(None)
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn optimize(name: &str, sign: f64, args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    if args.len() != 2 && args.len() != 3 {
+        return Err(Error::ArgumentCount(name.to_string(), 2, args.len(), pos.clone(), file_path.to_string()))
+    }
+    let func = expect_callable(name, &args[0], file_path)?;
+    let scalar = !matches!(args[1].0, Value::Vector(_));
+    let x0 = expect_point(name, &args[1], file_path)?;
+    let bounds = match args.get(2) {
+        Some(arg) => {
+            let [lo, hi] = expect_vector(name, arg, file_path)?.as_slice() else {
+                return Err(Error::Shape(2, expect_vector(name, arg, file_path)?.len(), arg.1.clone(), file_path.to_string()))
+            };
+            let side = |value: &Value| -> Result<Vec<f64>, Error> {
+                let side = expect_point(name, &(value.clone(), arg.1.clone()), file_path)?;
+                match side.len() {
+                    1 => Ok(vec![side[0]; x0.len()]),
+                    len if len == x0.len() => Ok(side),
+                    len => Err(Error::Shape(x0.len(), len, arg.1.clone(), file_path.to_string()))
+                }
+            };
+            let (lo, hi) = (side(lo)?, side(hi)?);
+            if lo.iter().zip(&hi).any(|(lo, hi)| lo > hi) {
+                return Err(Error::Domain(format!("{name} expects lower bounds below upper bounds"), arg.1.clone(), file_path.to_string()))
+            }
+            Some((lo, hi))
+        }
+        None => None
+    };
+    let spread = matches!(&func, Value::Function(params, _) if params.len() == x0.len() && x0.len() > 1);
+    let mut f = Objective { func: &func, spread, scalar, sign, bounds, evaluations: 0, pos, file_path, context };
+    let u0 = f.outside(&x0);
+    let result = match bfgs(&mut f, &u0)? {
+        Some(result) => Some(result),
+        None => nelder_mead(&mut f, &u0)?
+    };
+    let result = match result {
+        Some((x, fx, iterations)) if minimum(&mut f, &x, fx)? => Some((x, fx, iterations)),
+        _ => None
+    };
+    let Some((x, fx, iterations)) = result else {
+        return Err(Error::Converge(format!("{name} from {}", args[1].0), pos.clone(), file_path.to_string()))
+    };
+    let x = f.inside(&x);
+    Ok(Value::Vector(vec![f.point(&x), Value::Float(sign * fx), Value::Int(iterations), Value::Int(f.evaluations)]))
+}
+pub fn minimize(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    optimize("minimize", 1.0, args, pos, file_path, context)
+}
+pub fn maximize(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    optimize("maximize", -1.0, args, pos, file_path, context)
+}
+pub fn golden(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("golden", args, 3, pos, file_path)?;
+    let func = expect_callable("golden", &args[0], file_path)?;
+    let a = expect_float("golden", &args[1], file_path)?;
+    let b = expect_float("golden", &args[2], file_path)?;
+    if !a.is_finite() || !b.is_finite() {
+        return Err(Error::Domain("golden expects finite bounds".to_string(), pos.clone(), file_path.to_string()))
+    }
+    let mut f = Objective { func: &func, spread: false, scalar: true, sign: 1.0, bounds: None, evaluations: 0, pos, file_path, context };
+    let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let (mut lo, mut hi) = (a.min(b), a.max(b));
+    let (mut x1, mut x2) = (hi - ratio * (hi - lo), lo + ratio * (hi - lo));
+    let (mut f1, mut f2) = (f.eval(&[x1])?, f.eval(&[x2])?);
+    let mut iterations = 0;
+    while hi - lo > 1e-10 * (1.0 + lo.abs().max(hi.abs())) {
+        iterations += 1;
+        if f1 <= f2 {
+            (hi, x2, f2) = (x2, x1, f1);
+            x1 = hi - ratio * (hi - lo);
+            f1 = f.eval(&[x1])?;
+        } else {
+            (lo, x1, f1) = (x1, x2, f2);
+            x2 = lo + ratio * (hi - lo);
+            f2 = f.eval(&[x2])?;
+        }
+    }
+    let (x, fx) = if f1 <= f2 { (x1, f1) } else { (x2, f2) };
+    Ok(Value::Vector(vec![Value::Float(x), Value::Float(fx), Value::Int(iterations), Value::Int(f.evaluations)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use super::*;
+
+    fn point(value: &Value) -> Vec<f64> {
+        match value {
+            Value::Vector(x) => x.iter().map(|x| x.number().unwrap()).collect(),
+            x => vec![x.number().unwrap()]
+        }
+    }
+    fn result(lines: &[&str]) -> (Vec<f64>, f64) {
+        let Value::Vector(result) = eval_lines(lines).unwrap() else { panic!("expected a result vector") };
+        (point(&result[0]), result[1].number().unwrap())
+    }
+    fn with_objective(definition: &str, test: &dyn Fn(&mut Objective)) {
+        let func = eval_lines(&[definition]).unwrap();
+        let (pos, mut context) = (Position::new(0, 0, 0, 0, 0, 0), Context::seeded(0));
+        let mut f = Objective {
+            func: &func, spread: true, scalar: false, sign: 1.0, bounds: None, evaluations: 0, pos: &pos, file_path: "<test>", context: &mut context
+        };
+        test(&mut f)
+    }
+
+    #[test]
+    fn bfgs_and_nelder_mead_find_the_rosenbrock_minimum() {
+        with_objective("rosen(x y) : (1 - x)^2 + 100 * (y - x^2)^2", &|f| {
+            let (x, fx, _) = bfgs(f, &[-1.2, 1.0]).unwrap().unwrap();
+            assert!(x.iter().all(|x| (x - 1.0).abs() < 1e-6) && fx < 1e-12);
+            let (x, fx, _) = nelder_mead(f, &[-1.2, 1.0]).unwrap().unwrap();
+            assert!(x.iter().all(|x| (x - 1.0).abs() < 1e-6) && fx < 1e-12);
+            assert!(minimum(f, &[1.0, 1.0], 0.0).unwrap());
+            assert!(!minimum(f, &[0.0, 0.0], 1.0).unwrap());
+        });
+    }
+    #[test]
+    fn large_offsets_do_not_stop_the_descent_early() {
+        let (x, _) = result(&["f(x) : (x - 2)^2 + 10^12", "minimize(f 0)"]);
+        assert!((x[0] - 2.0).abs() < 1e-3);
+        let (x, _) = result(&["g(x y) : (x - 2)^2 + (y + 1)^2 + 10^12", "minimize(g [0 0])"]);
+        assert!((x[0] - 2.0).abs() < 1e-3 && (x[1] + 1.0).abs() < 1e-3);
+    }
+    #[test]
+    fn bounds_and_maximize() {
+        let (x, fx) = result(&["b(x y) : (x - 1)^2 + (y + 2)^2", "minimize(b [0 0] [[0 0] [0.5 5]])"]);
+        assert!((x[0] - 0.5).abs() < 1e-6 && x[1].abs() < 1e-6 && (fx - 4.25).abs() < 1e-9);
+        let (x, fx) = result(&["maximize(sin 0)"]);
+        assert!((x[0] - PI / 2.0).abs() < 1e-6 && (fx - 1.0).abs() < 1e-12);
+    }
+    #[test]
+    fn unbounded_functions_do_not_converge() {
+        for lines in [["l(x) : x", "minimize(l 0)"], ["c(x) : x^3", "minimize(c 1)"]] {
+            assert!(matches!(eval_lines(&lines), Err(Error::Converge(_, _, _))));
+        }
+    }
+    #[test]
+    fn golden_needs_finite_bounds() {
+        let (x, fx) = result(&["golden(cos 0 2*pi)"]);
+        assert!((x[0] - PI).abs() < 1e-6 && (fx + 1.0).abs() < 1e-12);
+        assert!(matches!(eval_lines(&["golden(cos 0 inf)"]), Err(Error::Domain(_, _, _))));
+        assert!(matches!(eval_lines(&["golden(cos 0-inf 0)"]), Err(Error::Domain(_, _, _))));
+    }
+}
\ No newline at end of file
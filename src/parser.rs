@@ -7,7 +7,12 @@ pub enum Node {
     Binary(Token, (Box<Node>, Position), (Box<Node>, Position)), Unary(Token, (Box<Node>, Position)),
     Int(i64), Float(f64), Infinity, PI, Variable(String), Vector(Vec<(Node, Position)>),
     Set((Box<Node>, Position), (Box<Node>, Position)),
-    Call((Box<Node>, Position), (Box<Node>, Position)),
+    Call((Box<Node>, Position), Vec<(Node, Position)>),
+    Range((Box<Node>, Position), (Box<Node>, Position), Option<(Box<Node>, Position)>),
+    Series(Token, String, (Box<Node>, Position), (Box<Node>, Position)),
+    Diff((Box<Node>, Position), String),
+    Solve(Vec<(Node, Position)>),
+    Limit(String, Vec<(Node, Position)>),
 }
 // impl Node {
 //     pub fn name(&self) -> &str {
@@ -23,25 +28,76 @@ pub enum Node {
 //         }
 //     }
 // }
+impl Node {
+    pub fn substitute(&self, var: &str, with: &Node) -> Node {
+        let sub = |(node, pos): &(Box<Node>, Position)| (Box::new(node.substitute(var, with)), pos.clone());
+        match self {
+            Self::Variable(name) if name == var => with.clone(),
+            Self::Binary(op, left, right) => Self::Binary(op.clone(), sub(left), sub(right)),
+            Self::Unary(op, node) => Self::Unary(op.clone(), sub(node)),
+            Self::Vector(nodes) => Self::Vector(nodes.iter().map(|(node, pos)| (node.substitute(var, with), pos.clone())).collect()),
+            Self::Set(target, expr) => Self::Set(target.clone(), sub(expr)),
+            Self::Call(func, args) => Self::Call(func.clone(), args.iter().map(|(node, pos)| (node.substitute(var, with), pos.clone())).collect()),
+            Self::Range(start, end, step) => Self::Range(sub(start), sub(end), step.as_ref().map(sub)),
+            Self::Series(op, bound, values, body) =>
+            Self::Series(op.clone(), bound.clone(), sub(values), if bound == var { body.clone() } else { sub(body) }),
+            Self::Diff(func, bound) => Self::Diff(sub(func), bound.clone()),
+            Self::Solve(args) => Self::Solve(args.iter().map(|(node, pos)| (node.substitute(var, with), pos.clone())).collect()),
+            Self::Limit(name, args) => Self::Limit(name.clone(), args.iter().map(|(node, pos)| (node.substitute(var, with), pos.clone())).collect()),
+            _ => self.clone()
+        }
+    }
+}
+impl Node {
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Set(_, _) | Self::Series(_, _, _, _) | Self::Binary(Token::Where, _, _) => 0,
+            Self::Binary(Token::Equal | Token::NotEqual | Token::Greater | Token::Less
+                | Token::GreaterEqual | Token::LessEqual, _, _) => 1,
+            Self::Range(_, _, _) => 2,
+            Self::Binary(Token::Add | Token::Subtract | Token::Concat, _, _) => 3,
+            Self::Binary(Token::Multiply | Token::Divide, _, _) => 4,
+            Self::Binary(Token::Power, _, _) => 5,
+            Self::Unary(Token::Subtract, _) => 6,
+            Self::Int(int) if *int < 0 => 6,
+            Self::Float(float) if *float < 0.0 => 6,
+            Self::Call(_, _) | Self::Diff(_, _) | Self::Solve(_) | Self::Limit(_, _) | Self::Unary(_, _) => 7,
+            Self::Binary(_, _, _) => 8,
+            _ => 9
+        }
+    }
+    fn wrap(&self, min: u8) -> String {
+        if self.precedence() < min { format!("({self})") } else { format!("{self}") }
+    }
+}
 impl Display for Node {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FMTError> {
+        let join = |nodes: &Vec<(Node, Position)>| nodes.iter().map(|(node, _)| format!("{node}")).collect::<Vec<String>>().join(" ");
         match &self {
-            Self::Int(int) => write!(f, "({int})"),
-            Self::Float(float) => write!(f, "({float})"),
-            Self::PI => write!(f, "(pi)"),
-            Self::Infinity => write!(f, "(inf)"),
-            Self::Variable(var) => write!(f, "({var})"),
-            Self::Set((var, _), (expr, _)) => write!(f, "({var} : {expr})"),
-            Self::Call((var, _), (param, _)) => write!(f, "({var} ( {param} ))"),
-            Self::Vector(vector) => {
-                let mut strings: Vec<String> = vec![];
-                for (node, _) in vector {
-                    strings.push(format!("{node}"));
-                }
-                write!(f, "[{}]", strings.join(" "))
+            Self::Int(int) => write!(f, "{int}"),
+            Self::Float(float) => write!(f, "{float}"),
+            Self::PI => write!(f, "pi"),
+            Self::Infinity => write!(f, "inf"),
+            Self::Variable(var) => write!(f, "{var}"),
+            Self::Set((var, _), (expr, _)) => write!(f, "{} : {expr}", var.wrap(1)),
+            Self::Call((var, _), args) => write!(f, "{}({})", var.wrap(8), join(args)),
+            Self::Diff((func, _), var) => write!(f, "diff({func} {var})"),
+            Self::Solve(args) => write!(f, "solve({})", join(args)),
+            Self::Limit(name, args) => write!(f, "{name}({})", join(args)),
+            Self::Vector(vector) => write!(f, "[{}]", join(vector)),
+            Self::Range((start, _), (end, _), step) => match step {
+                Some((step, _)) => write!(f, "{}..{} step {}", start.wrap(3), end.wrap(3), step.wrap(3)),
+                None => write!(f, "{}..{}", start.wrap(3), end.wrap(3)),
             },
-            Self::Binary(op, (left, _), (right, _)) => write!(f, "({left} {} {right})", op.name()),
-            Self::Unary(op, (node, _)) => write!(f, "({} {node})", op.name()),
+            Self::Series(op, var, (values, _), (body, _)) => write!(f, "{} {var}={} : {body}", op.symbol(), values.wrap(2)),
+            Self::Binary(Token::Power, (left, _), (right, _)) => write!(f, "{}^{}", left.wrap(7), right.wrap(7)),
+            Self::Binary(Token::Hashtag, (left, _), (right, _)) => write!(f, "{} # {}", left.wrap(9), right.wrap(6)),
+            Self::Binary(op, (left, _), (right, _)) => {
+                let level = self.precedence();
+                write!(f, "{} {} {}", left.wrap(level), op.symbol(), right.wrap(level + 1))
+            }
+            Self::Unary(Token::Factorial, (node, _)) => write!(f, "{}!", node.wrap(8)),
+            Self::Unary(op, (node, _)) => write!(f, "{}{}", op.symbol(), node.wrap(7)),
         }
     }
 }
@@ -76,8 +132,11 @@ impl Parser {
         Ok(node)
     }
     pub fn expr(&mut self) -> Result<(Node, Position), Error> {
-        let (mut left, mut left_pos) = self.comp()?;
+        let (mut left, mut left_pos) = self.subst()?;
         while self.token() == Token::Rep {
+            if let Some(name) = Self::special_name(&left) { return Err(Error::Syntax(
+                format!("{name} is a special form and cannot be redefined"), left_pos, self.file_path.clone()
+            )) }
             self.advance();
             let (right, right_pos) = self.expr()?;
             left = Node::Set((Box::new(left), left_pos.clone()), (Box::new(right), right_pos.clone()));
@@ -85,21 +144,60 @@ impl Parser {
         }
         Ok((left, left_pos))
     }
+    pub fn subst(&mut self) -> Result<(Node, Position), Error> {
+        let (mut left, mut left_pos) = self.comp()?;
+        let binding = |node: &Node| matches!(node, Node::Binary(Token::Equal, (var, _), _) if matches!(var.as_ref(), Node::Variable(_)));
+        while self.token() == Token::Where {
+            self.advance();
+            let (right, right_pos) = self.comp()?;
+            let valid = match &right {
+                Node::Vector(nodes) => nodes.iter().all(|(node, _)| binding(node)),
+                node => binding(node)
+            };
+            if !valid { return Err(Error::Syntax(
+                format!("expected bindings like x=3 after '|' got {right}"), right_pos, self.file_path.clone()
+            )) }
+            left = Node::Binary(Token::Where, (Box::new(left), left_pos.clone()), (Box::new(right), right_pos.clone()));
+            left_pos.extend(right_pos);
+        }
+        Ok((left, left_pos))
+    }
     pub fn comp(&mut self) -> Result<(Node, Position), Error> {
-        let (mut left, mut left_pos) = self.arith()?;
+        let (mut left, mut left_pos) = self.range()?;
         while [Token::Equal, Token::NotEqual, Token::Greater, Token::Less, Token::GreaterEqual, Token::LessEqual]
         .contains(&self.token()) {
             let op = self.token();
             self.advance();
-            let (right, right_pos) = self.arith()?;
+            let (right, right_pos) = self.range()?;
             left = Node::Binary(op, (Box::new(left), left_pos.clone()), (Box::new(right), right_pos.clone()));
             left_pos.extend(right_pos);
         }
         Ok((left, left_pos))
     }
+    pub fn range(&mut self) -> Result<(Node, Position), Error> {
+        let (start, start_pos) = self.arith()?;
+        self.range_bounds(start, start_pos, Self::arith)
+    }
+    pub fn range_bounds(&mut self, start: Node, start_pos: Position,
+        bound: fn(&mut Self) -> Result<(Node, Position), Error>) -> Result<(Node, Position), Error> {
+        if self.token() != Token::Range { return Ok((start, start_pos)) }
+        self.advance();
+        let mut pos = start_pos.clone();
+        let (end, end_pos) = bound(self)?;
+        pos.extend(end_pos.clone());
+        let mut step: Option<(Box<Node>, Position)> = None;
+        // step is a keyword only after the bounds of a range, elsewhere it is an ordinary name
+        if self.token() == Token::Variable("step".to_string()) {
+            self.advance();
+            let (step_node, step_pos) = bound(self)?;
+            pos.extend(step_pos.clone());
+            step = Some((Box::new(step_node), step_pos));
+        }
+        Ok((Node::Range((Box::new(start), start_pos), (Box::new(end), end_pos), step), pos))
+    }
     pub fn arith(&mut self) -> Result<(Node, Position), Error> {
         let (mut left, mut left_pos) = self.term()?;
-        while [Token::Add, Token::Subtract].contains(&self.token()) {
+        while [Token::Add, Token::Subtract, Token::Concat].contains(&self.token()) {
             let op = self.token();
             self.advance();
             let (right, right_pos) = self.term()?;
@@ -141,28 +239,95 @@ impl Parser {
     }
     pub fn call(&mut self) -> Result<(Node, Position), Error> {
         let mut pos = self.pos();
-        let (var, var_pos) = self.hash()?;
-        if self.token() == Token::GroupIn {
+        let (mut var, mut var_pos) = self.hash()?;
+        let callable = !matches!(var, Node::Int(_) | Node::Float(_) | Node::Infinity | Node::PI | Node::Vector(_));
+        if self.token() == Token::GroupIn && callable {
             self.advance();
-            let (arg, arg_pos) = self.expr()?;
-            self.expect_token(Token::GroupOut)?;
+            let mut args: Vec<(Node, Position)> = vec![];
+            while self.token() != Token::GroupOut {
+                args.push(self.expr()?);
+            }
             let pos_ = self.pos();
             pos.extend(pos_);
             self.advance();
-            return Ok((Node::Call((Box::new(var), var_pos), (Box::new(arg), arg_pos)), pos))
+            var = match Self::special_call(&var, &mut args) {
+                Some(special) => special,
+                None => Node::Call((Box::new(var), var_pos), args)
+            };
+            var_pos = pos;
+        }
+        while self.token() == Token::Factorial {
+            let mut pos_ = var_pos.clone();
+            pos_.extend(self.pos());
+            self.advance();
+            var = Node::Unary(Token::Factorial, (Box::new(var), var_pos));
+            var_pos = pos_;
         }
         Ok((var, var_pos))
     }
+    // calls to these names are rewritten by shape, so a definition under one of them could never be called
+    fn special_name(target: &Node) -> Option<String> {
+        let reserved = |node: &Node| match node {
+            Node::Variable(name) if ["sum", "prod", "diff", "solve", "limit", "limitl", "limitr"].contains(&name.as_str()) => Some(name.clone()),
+            _ => None
+        };
+        match target {
+            Node::Call((func, _), params) => reserved(func).or_else(|| params.iter().find_map(|(param, _)| reserved(param))),
+            Node::Series(Token::Product, _, _, _) => Some("prod".to_string()),
+            Node::Series(_, _, _, _) => Some("sum".to_string()),
+            Node::Diff(_, _) => Some("diff".to_string()),
+            Node::Solve(_) => Some("solve".to_string()),
+            Node::Limit(name, _) => Some(name.clone()),
+            node => reserved(node)
+        }
+    }
+    fn special_call(var: &Node, args: &mut Vec<(Node, Position)>) -> Option<Node> {
+        let op = match var {
+            Node::Variable(name) if name == "sum" => Token::Sum,
+            Node::Variable(name) if name == "prod" => Token::Product,
+            Node::Variable(name) if name == "diff" => {
+                let [_, (Node::Variable(bound), _)] = args.as_slice() else { return None };
+                let bound = bound.clone();
+                args.pop();
+                let (func, func_pos) = args.pop()?;
+                return Some(Node::Diff((Box::new(func), func_pos), bound))
+            }
+            Node::Variable(name) if name == "solve" => return Some(Node::Solve(std::mem::take(args))),
+            Node::Variable(name) if ["limit", "limitl", "limitr"].contains(&name.as_str()) =>
+                return Some(Node::Limit(name.clone(), std::mem::take(args))),
+            _ => return None
+        };
+        let [(Node::Variable(bound), _), _, _, _] = args.as_slice() else { return None };
+        let bound = bound.clone();
+        let (body, body_pos) = args.pop()?;
+        let (end, end_pos) = args.pop()?;
+        let (start, start_pos) = args.pop()?;
+        let mut values_pos = start_pos.clone();
+        values_pos.extend(end_pos.clone());
+        let values = Node::Range((Box::new(start), start_pos), (Box::new(end), end_pos), None);
+        Some(Node::Series(op, bound, (Box::new(values), values_pos), (Box::new(body), body_pos)))
+    }
     pub fn hash(&mut self) -> Result<(Node, Position), Error> {
         let (mut left, mut left_pos) = self.atom()?;
         while self.token() == Token::Hashtag {
             self.advance();
-            let (right, right_pos) = self.atom()?;
+            let (right, right_pos) = self.signed_atom()?;
+            let (right, right_pos) = self.range_bounds(right, right_pos, Self::signed_atom)?;
             left = Node::Binary(Token::Hashtag, (Box::new(left), left_pos.clone()), (Box::new(right), right_pos.clone()));
             left_pos.extend(right_pos);
         }
         Ok((left, left_pos))
     }
+    pub fn signed_atom(&mut self) -> Result<(Node, Position), Error> {
+        let mut pos = self.pos();
+        if self.token() == Token::Subtract {
+            self.advance();
+            let (node, node_pos) = self.atom()?;
+            pos.extend(node_pos.clone());
+            return Ok((Node::Unary(Token::Subtract,(Box::new(node), node_pos)), pos))
+        }
+        self.atom()
+    }
     pub fn atom(&mut self) -> Result<(Node, Position), Error> {
         match self.token() {
             Token::Int(int) => {
@@ -185,6 +350,23 @@ impl Parser {
                 self.advance();
                 Ok((Node::PI, self.pos()))
             }
+            Token::Sum | Token::Product => {
+                let op = self.token();
+                let mut pos = self.pos();
+                self.advance();
+                let Token::Variable(var) = self.token() else {
+                    return Err(Error::ExpectToken(Token::Variable("".to_string()), self.token(), self.pos(), self.file_path.clone()))
+                };
+                self.advance();
+                self.expect_token(Token::Equal)?;
+                self.advance();
+                let (values, values_pos) = self.range()?;
+                self.expect_token(Token::Rep)?;
+                self.advance();
+                let (body, body_pos) = self.expr()?;
+                pos.extend(body_pos.clone());
+                Ok((Node::Series(op, var, (Box::new(values), values_pos), (Box::new(body), body_pos)), pos))
+            }
             Token::GroupIn => {
                 self.advance();
                 let node = self.expr()?;
@@ -211,4 +393,53 @@ impl Parser {
 }
 pub fn parse(tokens: Vec<(Token, Position)>, file_path: &str) -> Result<(Node, Position), Error> {
     Parser::new(tokens, file_path.to_string()).parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::eval_lines;
+
+    fn shown(lines: &[&str]) -> String {
+        match eval_lines(lines) {
+            Ok(value) => value.to_string(),
+            Err(error) => error.to_string()
+        }
+    }
+
+    #[test]
+    fn sum_and_prod_calls_become_series() {
+        let (node, _) = parse(lex("sum(k 1 4 k^2)\n", "<test>").unwrap(), "<test>").unwrap();
+        assert!(matches!(node, Node::Series(Token::Sum, ref bound, _, _) if bound == "k"));
+        assert_eq!(shown(&["sum(k 1 4 k^2)"]), "30");
+        assert_eq!(shown(&["prod(k 1 5 k)"]), "120");
+        assert_eq!(shown(&["n : 3", "sum(k 1 n k*n)"]), "18");
+    }
+    #[test]
+    fn sigma_and_pi_run_over_ranges_and_vectors() {
+        assert_eq!(shown(&["Σ k=1..10 : k^2"]), "385");
+        assert_eq!(shown(&["Π k=1..5 : k"]), "120");
+        assert_eq!(shown(&["Σ k=[1 4 9] : 1/k"]), "1.3611111111111112");
+    }
+    #[test]
+    fn empty_series_give_their_identity() {
+        assert_eq!(shown(&["sum(k 5 1 k)"]), "0");
+        assert_eq!(shown(&["prod(k 5 1 k)"]), "1");
+        assert_eq!(shown(&["Σ k=1..0 : k"]), "0");
+        assert_eq!(shown(&["Π k=[] : k"]), "1");
+    }
+    #[test]
+    fn special_forms_cannot_be_redefined() {
+        for line in ["sum : 3", "prod(v) : v", "sum(a b c d) : a", "diff(f x) : f", "solve : 0", "limitr(f a) : 0", "g(sum) : sum(k 1 2 k)"] {
+            let error = parse(lex(&format!("{line}\n"), "<test>").unwrap(), "<test>").unwrap_err();
+            assert!(error.to_string().contains("is a special form and cannot be redefined"), "{line}");
+        }
+        assert_eq!(shown(&["total(v) : sum(k 0 2 v # k)", "total([1 2 3])"]), "6");
+        assert_eq!(shown(&["s : 3", "s"]), "3");
+    }
+    #[test]
+    fn the_bound_variable_stays_inside_the_series() {
+        assert_eq!(shown(&["k : 7", "sum(k 1 3 k)", "k"]), "7");
+        assert!(shown(&["sum(j 1 3 j)", "j"]).starts_with("ERROR: j not defined"));
+    }
 }
\ No newline at end of file
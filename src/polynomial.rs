@@ -0,0 +1,382 @@
+use std::fmt::{Display, Formatter, Error as FMTError};
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::parser::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::complex::*;
+use crate::dual::*;
+use crate::symbolic::*;
+
+const MAX_DEGREE: u128 = 10000;
+
+#[derive(Clone, PartialEq)]
+pub struct Polynomial {
+    pub coeffs: Vec<f64>,
+    pub var: String
+}
+impl Polynomial {
+    pub fn new(mut coeffs: Vec<f64>, var: &str) -> Self {
+        while coeffs.last() == Some(&0.0) { coeffs.pop(); }
+        Self { coeffs, var: var.to_string() }
+    }
+    fn with(&self, coeffs: Vec<f64>) -> Self { Self::new(coeffs, &self.var) }
+    pub fn degree(&self) -> usize { self.coeffs.len().saturating_sub(1) }
+    pub fn is_zero(&self) -> bool { self.coeffs.is_empty() }
+    pub fn eval(&self, x: f64) -> f64 {
+        self.coeffs.iter().rev().fold(0.0, |acc, c| acc * x + c)
+    }
+    pub fn eval_complex(&self, z: Complex) -> Complex {
+        self.coeffs.iter().rev().fold(Complex::new(0.0, 0.0), |acc, c| acc * z + Complex::new(*c, 0.0))
+    }
+    pub fn add(&self, other: &Self) -> Self {
+        self.with((0..self.coeffs.len().max(other.coeffs.len())).map(|i| {
+            self.coeffs.get(i).unwrap_or(&0.0) + other.coeffs.get(i).unwrap_or(&0.0)
+        }).collect())
+    }
+    pub fn scale(&self, factor: f64) -> Self {
+        self.with(self.coeffs.iter().map(|c| c * factor).collect())
+    }
+    pub fn mul(&self, other: &Self) -> Self {
+        if self.is_zero() || other.is_zero() { return self.with(vec![]) }
+        let mut coeffs = vec![0.0; self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, a) in self.coeffs.iter().enumerate() {
+            for (j, b) in other.coeffs.iter().enumerate() { coeffs[i + j] += a * b }
+        }
+        self.with(coeffs)
+    }
+    pub fn divmod(&self, other: &Self) -> Option<(Self, Self)> {
+        let lead = *other.coeffs.last()?;
+        let mut rem = self.coeffs.clone();
+        if rem.len() < other.coeffs.len() { return Some((self.with(vec![]), self.clone())) }
+        let mut quot = vec![0.0; rem.len() - other.coeffs.len() + 1];
+        for i in (0..quot.len()).rev() {
+            quot[i] = rem[i + other.degree()] / lead;
+            for (j, c) in other.coeffs.iter().enumerate() { rem[i + j] -= quot[i] * c }
+        }
+        let scale = self.coeffs.iter().fold(0.0_f64, |acc, c| acc.max(c.abs()));
+        rem.truncate(other.degree());
+        Some((self.with(quot), self.with(rem.into_iter().map(|c| if c.abs() <= 1e-12 * scale { 0.0 } else { c }).collect())))
+    }
+    pub fn powi(&self, exp: u64) -> Self {
+        let (mut result, mut base, mut n) = (self.with(vec![1.0]), self.clone(), exp);
+        while n > 0 {
+            if n & 1 == 1 { result = result.mul(&base) }
+            n >>= 1;
+            if n > 0 { base = base.mul(&base) }
+        }
+        result
+    }
+    pub fn derivative(&self) -> Self {
+        self.with(self.coeffs.iter().enumerate().skip(1).map(|(i, c)| c * i as f64).collect())
+    }
+    pub fn integral(&self) -> Self {
+        self.with([vec![0.0], self.coeffs.iter().enumerate().map(|(i, c)| c / (i + 1) as f64).collect()].concat())
+    }
+    pub fn gcd(&self, other: &Self) -> Self {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while let Some((_, rem)) = a.divmod(&b) { (a, b) = (b, rem) }
+        match a.coeffs.last() {
+            Some(lead) => a.scale(1.0 / lead),
+            None => a
+        }
+    }
+    pub fn roots(&self) -> Vec<Complex> {
+        let repeated = self.gcd(&self.derivative());
+        let mut z = match self.divmod(&repeated) {
+            Some((simple, _)) if repeated.degree() > 0 => [simple.simple_roots(), repeated.roots()].concat(),
+            _ => self.simple_roots()
+        };
+        z.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+        z
+    }
+    fn simple_roots(&self) -> Vec<Complex> {
+        let n = self.degree();
+        let Some(lead) = self.coeffs.last().filter(|_| n > 0) else { return vec![] };
+        let monic = self.scale(1.0 / lead);
+        let radius = 1.0 + monic.coeffs.iter().take(n).fold(0.0_f64, |acc, c| acc.max(c.abs()));
+        let mut z: Vec<Complex> = (0..n).map(|k| Complex::polar(radius, 0.4 + 2.0 * std::f64::consts::PI * k as f64 / n as f64)).collect();
+        for _ in 0..1000 {
+            let mut change = 0.0_f64;
+            for i in 0..n {
+                let den = (0..n).filter(|j| *j != i).fold(Complex::new(1.0, 0.0), |acc, j| acc * (z[i] - z[j]));
+                if den.norm() == 0.0 { continue }
+                let step = monic.eval_complex(z[i]) / den;
+                z[i] = z[i] - step;
+                change = change.max(step.norm() / (1.0 + z[i].norm()));
+            }
+            if change < 1e-15 { break }
+        }
+        let slope = monic.derivative();
+        for root in z.iter_mut() {
+            for _ in 0..3 {
+                let den = slope.eval_complex(*root);
+                if den.norm() == 0.0 { break }
+                *root = *root - monic.eval_complex(*root) / den;
+            }
+            if root.im.abs() <= 1e-10 * (1.0 + root.norm()) { root.im = 0.0 }
+            let snap = |x: f64| fraction(x).map_or(x, |(num, den)| num as f64 / den as f64);
+            let snapped = Complex::new(snap(root.re), snap(root.im));
+            if monic.eval_complex(snapped).norm() <= monic.eval_complex(*root).norm() { *root = snapped }
+        }
+        let mut paired = vec![false; n];
+        for i in 0..n {
+            if z[i].im <= 0.0 || paired[i] { continue }
+            let partner = (0..n).filter(|j| !paired[*j] && z[*j].im < 0.0)
+                .min_by(|a, b| (z[*a] - z[i].conj()).norm().total_cmp(&(z[*b] - z[i].conj()).norm()));
+            if let Some(j) = partner {
+                z[i] = Complex::new((z[i].re + z[j].re) / 2.0, (z[i].im - z[j].im) / 2.0);
+                z[j] = z[i].conj();
+                (paired[i], paired[j]) = (true, true);
+            }
+        }
+        z
+    }
+    pub fn node(&self, pos: &Position) -> Node {
+        simplify_node(&poly_node(&self.coeffs, &self.var, pos), pos)
+    }
+}
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FMTError> {
+        if self.is_zero() { return write!(f, "0") }
+        let mut first = true;
+        for (i, c) in self.coeffs.iter().enumerate().rev() {
+            if *c == 0.0 { continue }
+            let sign = match (first, *c < 0.0) {
+                (true, true) => "-",
+                (true, false) => "",
+                (false, true) => " - ",
+                (false, false) => " + "
+            };
+            let coeff = if c.abs() == 1.0 && i > 0 { "".to_string() } else { format!("{}", c.abs()) };
+            let power = match i {
+                0 => "".to_string(),
+                1 => self.var.clone(),
+                _ => format!("{}^{i}", self.var)
+            };
+            write!(f, "{sign}{coeff}{power}")?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+// -- ARITHMETIC -----------------------------------------------------------------------
+
+pub fn poly_binary(op: &Token, left: &Value, right: &Value, pos: &Position, file_path: &str) -> Result<Option<Value>, Error> {
+    let constant = |value: &Value, var: &str| value.number().map(|c| Polynomial::new(vec![c], var));
+    let (l, r) = match (left, right) {
+        (Value::Poly(l), Value::Poly(r)) => (l.clone(), r.clone()),
+        (Value::Poly(l), _) => match constant(right, &l.var) { Some(r) => (l.clone(), r), None => return Ok(None) },
+        (_, Value::Poly(r)) => match constant(left, &r.var) { Some(l) => (l, r.clone()), None => return Ok(None) },
+        _ => return Ok(None)
+    };
+    Ok(match op {
+        Token::Add => Some(Value::Poly(l.add(&r))),
+        Token::Subtract => Some(Value::Poly(l.add(&r.scale(-1.0)))),
+        Token::Multiply => Some(Value::Poly(l.mul(&r))),
+        Token::Divide if r.degree() == 0 && !r.is_zero() => Some(Value::Poly(l.scale(1.0 / r.coeffs[0]))),
+        Token::Power => match right {
+            Value::Int(exp) if *exp >= 0 => {
+                let degree = l.degree() as u128 * *exp as u128;
+                if degree > MAX_DEGREE { return Err(Error::Domain(
+                    format!("polynomial power of degree {degree} is too large"), pos.clone(), file_path.to_string()
+                )) }
+                Some(Value::Poly(l.powi(*exp as u64)))
+            }
+            _ => None
+        }
+        Token::Equal => Some(Value::Bool(l.coeffs == r.coeffs)),
+        Token::NotEqual => Some(Value::Bool(l.coeffs != r.coeffs)),
+        _ => None
+    })
+}
+pub fn poly_eval(p: &Polynomial, arg: &(Value, Position), file_path: &str) -> Result<Value, Error> {
+    match &arg.0 {
+        Value::Vector(values) => Ok(Value::Vector(values.iter()
+            .map(|value| poly_eval(p, &(value.clone(), arg.1.clone()), file_path))
+            .collect::<Result<Vec<Value>, Error>>()?)),
+        Value::Complex(z) => Ok(Value::Complex(p.eval_complex(*z))),
+        Value::Dual(x) => Ok(Value::Dual(p.coeffs.iter().rev().fold(Dual::constant(0.0, x), |acc, c| acc * x.clone() + Dual::constant(*c, x)))),
+        Value::Expr(node) => Ok(expression(simplify_node(&p.node(&arg.1).substitute(&p.var, node), &arg.1))),
+        value => Ok(Value::Float(p.eval(value.number().ok_or_else(|| {
+            Error::IllegalValue(value.clone(), Type::Poly, arg.1.clone(), file_path.to_string())
+        })?)))
+    }
+}
+
+// -- FACTORING ------------------------------------------------------------------------
+
+fn gcd_i128(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 { (a, b) = (b, a % b) }
+    a.abs()
+}
+fn primitive(coeffs: Vec<i128>) -> (i128, Vec<i128>) {
+    let content = coeffs.iter().fold(0, |acc, c| gcd_i128(acc, *c));
+    if content == 0 { return (0, coeffs) }
+    let content = if coeffs.last().is_some_and(|c| *c < 0) { -content } else { content };
+    (content, coeffs.into_iter().map(|c| c / content).collect())
+}
+fn exact_div(a: &[i128], b: &[i128]) -> Option<Vec<i128>> {
+    let lead = *b.last()?;
+    if b.len() > a.len() { return None }
+    let mut rem = a.to_vec();
+    let mut quot = vec![0; a.len() - b.len() + 1];
+    for i in (0..quot.len()).rev() {
+        let top = rem[i + b.len() - 1];
+        if top % lead != 0 { return None }
+        quot[i] = top / lead;
+        for (j, c) in b.iter().enumerate() { rem[i + j] = rem[i + j].checked_sub(quot[i].checked_mul(*c)?)? }
+    }
+    rem.iter().all(|c| *c == 0).then_some(quot)
+}
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 { return vec![vec![]] }
+    (k - 1..n).flat_map(|last| combinations(last, k - 1).into_iter().map(move |mut combo| { combo.push(last); combo })).collect()
+}
+// tries every set of up to half the roots as a factor, and gives up with None when that would take too long
+fn find_factor(rest: &[i128]) -> Option<Option<Vec<i128>>> {
+    let n = rest.len() - 1;
+    let lead = *rest.last()? as f64;
+    let roots = Polynomial::new(rest.iter().map(|c| *c as f64).collect(), "x").roots();
+    let mut budget = 200_000.0;
+    for k in 1..=n / 2 {
+        budget -= (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64);
+        if budget < 0.0 { return None }
+        for combo in combinations(n, k) {
+            let candidate = combo.iter().fold(vec![Complex::new(1.0, 0.0)], |acc, i| {
+                let mut next = vec![Complex::new(0.0, 0.0); acc.len() + 1];
+                for (j, c) in acc.iter().enumerate() {
+                    next[j] = next[j] - *c * roots[*i];
+                    next[j + 1] = next[j + 1] + *c;
+                }
+                next
+            });
+            if candidate.iter().any(|c| !(c.re * lead).is_finite() || (c.re * lead).abs() > 1e30) { continue }
+            let (_, factor) = primitive(candidate.iter().map(|c| (c.re * lead).round() as i128).collect());
+            if factor.last().is_none_or(|c| *c == 0) { continue }
+            if exact_div(rest, &factor).is_some() { return Some(Some(factor)) }
+        }
+    }
+    Some(None)
+}
+pub fn poly_factor(p: &Polynomial, pos: &Position, file_path: &str) -> Result<Value, Error> {
+    let irrational = || Error::Domain(format!("factor expects rational coefficients, got {p}"), pos.clone(), file_path.to_string());
+    let fractions: Vec<(i64, i64)> = p.coeffs.iter().map(|c| fraction(*c)).collect::<Option<Vec<(i64, i64)>>>().ok_or_else(irrational)?;
+    let denominator = fractions.iter().fold(1_i128, |acc, (_, den)| acc / gcd_i128(acc, *den as i128) * *den as i128);
+    let (content, mut rest) = primitive(fractions.iter().map(|(num, den)| *num as i128 * (denominator / *den as i128)).collect());
+    let mut factors: Vec<Value> = vec![];
+    let constant = content as f64 / denominator as f64;
+    if constant != 1.0 || rest.len() <= 1 { factors.push(Value::Poly(p.with(vec![constant]))) }
+    let as_poly = |coeffs: &[i128]| Value::Poly(p.with(coeffs.iter().map(|c| *c as f64).collect()));
+    while rest.len() > 2 {
+        let Some(factor) = find_factor(&rest).ok_or_else(|| Error::Domain(
+            format!("cannot fully factor {p}, it has too many roots to search"), pos.clone(), file_path.to_string()
+        ))? else { break };
+        rest = exact_div(&rest, &factor).ok_or_else(irrational)?;
+        factors.push(as_poly(&factor));
+    }
+    if rest.len() > 1 { factors.push(as_poly(&rest)) }
+    Ok(Value::Vector(factors))
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn expect_poly<'a>(name: &str, arg: &'a (Value, Position), file_path: &str) -> Result<&'a Polynomial, Error> {
+    match &arg.0 {
+        Value::Poly(p) => Ok(p),
+        _ => Err(illegal_arg(name, arg, file_path))
+    }
+}
+pub fn poly(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("poly", args, 1, pos, file_path)?;
+    match &args[0].0 {
+        Value::Poly(_) => Ok(args[0].0.clone()),
+        Value::Vector(values) => Ok(Value::Poly(Polynomial::new(
+            values.iter().map(|value| value.number().ok_or_else(|| illegal_arg("poly", &args[0], file_path))).collect::<Result<Vec<f64>, Error>>()?,
+            "x"
+        ))),
+        Value::Expr(node) => {
+            let [var] = free_vars(node).try_into().map_err(|vars: Vec<String>| Error::Domain(
+                format!("poly expects an expression in one variable, got {}", vars.join(" ")), args[0].1.clone(), file_path.to_string()
+            ))?;
+            let not_polynomial = || Error::Domain(format!("{node} is not a polynomial in {var}"), args[0].1.clone(), file_path.to_string());
+            let coeffs = polynomial_coeffs(node, &var, &args[0].1).ok_or_else(not_polynomial)?;
+            let mut scope = context.scope();
+            let mut values: Vec<f64> = vec![];
+            for coeff in &coeffs {
+                values.push(interpret((coeff, &args[0].1), file_path, &mut scope)?.number().ok_or_else(not_polynomial)?);
+            }
+            Ok(Value::Poly(Polynomial::new(values, &var)))
+        }
+        value => Ok(Value::Poly(Polynomial::new(vec![value.number().ok_or_else(|| illegal_arg("poly", &args[0], file_path))?], "x")))
+    }
+}
+pub fn coeffs(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("coeffs", args, 1, pos, file_path)?;
+    let p = expect_poly("coeffs", &args[0], file_path)?;
+    Ok(Value::Vector(p.coeffs.iter().map(|c| Value::Float(*c)).collect()))
+}
+pub fn deg(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("deg", args, 1, pos, file_path)?;
+    Ok(Value::Int(expect_poly("deg", &args[0], file_path)?.degree() as i64))
+}
+pub fn pdiv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("pdiv", args, 2, pos, file_path)?;
+    let p = expect_poly("pdiv", &args[0], file_path)?;
+    let q = match &args[1].0 {
+        Value::Poly(q) => q.clone(),
+        value => Polynomial::new(vec![value.number().ok_or_else(|| illegal_arg("pdiv", &args[1], file_path))?], &p.var)
+    };
+    let (quot, rem) = p.divmod(&q).ok_or_else(|| Error::Domain("pdiv by zero polynomial".to_string(), args[1].1.clone(), file_path.to_string()))?;
+    Ok(Value::Vector(vec![Value::Poly(quot), Value::Poly(rem)]))
+}
+pub fn roots(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("roots", args, 1, pos, file_path)?;
+    let p = expect_poly("roots", &args[0], file_path)?;
+    if p.is_zero() { return Err(Error::Domain("roots of zero polynomial".to_string(), args[0].1.clone(), file_path.to_string())) }
+    Ok(Value::Vector(p.roots().into_iter().map(|z| if z.im == 0.0 { Value::Float(z.re) } else { Value::Complex(z) }).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durand_kerner_finds_simple_and_repeated_roots() {
+        let roots = Polynomial::new(vec![-6.0, 11.0, -6.0, 1.0], "x").roots();
+        assert_eq!(roots.iter().map(|z| (z.re, z.im)).collect::<Vec<(f64, f64)>>(), vec![(1.0, 0.0), (2.0, 0.0), (3.0, 0.0)]);
+        let roots = Polynomial::new(vec![1.0, -2.0, 1.0], "x").roots();
+        assert_eq!(roots.iter().map(|z| (z.re, z.im)).collect::<Vec<(f64, f64)>>(), vec![(1.0, 0.0), (1.0, 0.0)]);
+    }
+    #[test]
+    fn complex_roots_come_in_conjugate_pairs() {
+        let p = Polynomial::new(vec![-1.0, 0.0, 0.0, 0.0, 0.0, 1.0], "x");
+        let roots = p.roots();
+        assert_eq!(roots.len(), 5);
+        assert!(roots.iter().all(|z| p.eval_complex(*z).norm() < 1e-12));
+        assert!(roots.iter().all(|z| roots.iter().any(|w| w.re == z.re && w.im == -z.im)));
+    }
+    #[test]
+    fn factor_splits_over_the_rationals() {
+        assert_eq!(eval_lines(&["factor(poly([(-6) 11 (-6) 1]))"]).unwrap().to_string(), "[x - 1, x - 2, x - 3]");
+        assert_eq!(eval_lines(&["factor(poly([(-2) 0 0 0 2]))"]).unwrap().to_string(), "[2, x + 1, x - 1, x^2 + 1]");
+        assert_eq!(eval_lines(&["factor(poly([2 0 1]))"]).unwrap().to_string(), "[x^2 + 2]");
+        assert!(eval_lines(&["roots(poly([0]))"]).is_err());
+    }
+    #[test]
+    fn powers_square_and_have_a_degree_limit() {
+        let x = Polynomial::new(vec![1.0, 1.0], "x");
+        assert_eq!(x.powi(3).coeffs, vec![1.0, 3.0, 3.0, 1.0]);
+        assert_eq!(x.powi(0).coeffs, vec![1.0]);
+        assert!(matches!(eval_lines(&["poly([0 1])^100000000"]), Err(Error::Domain(_, _, _))));
+    }
+    #[test]
+    fn factor_searches_every_grouping_or_refuses() {
+        let factors = eval_lines(&["factor(poly([1 0 1])^9)"]).unwrap().to_string();
+        assert_eq!(factors, format!("[{}]", ["x^2 + 1"; 9].join(", ")));
+        assert!(eval_lines(&["factor(poly([2 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 1]))"]).unwrap_err().to_string()
+            .starts_with("ERROR: cannot fully factor"));
+    }
+}
\ No newline at end of file
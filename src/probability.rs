@@ -0,0 +1,385 @@
+use std::f64::consts::PI;
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::dual::*;
+
+// -- SPECIAL FUNCTIONS ----------------------------------------------------------------
+
+pub fn ln_gamma(x: f64) -> f64 {
+    const G: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8, 771.323_428_777_653_1,
+        -176.615_029_162_140_6, 12.507_343_278_686_905, -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 { return (PI / (PI * x).sin()).abs().ln() - ln_gamma(1.0 - x) }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = G[1..].iter().enumerate().fold(G[0], |sum, (i, g)| sum + g / (x + i as f64 + 1.0));
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+// the series and continued fractions need on the order of sqrt(size) terms for large shapes
+fn terms(size: f64) -> usize {
+    (1000.0 + 10.0 * size.sqrt()).min(1e7) as usize
+}
+pub fn gamma_p(a: f64, x: f64) -> f64 {
+    if x <= 0.0 { return 0.0 }
+    if x < a + 1.0 {
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..terms(a.max(x)) {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-16 { break }
+        }
+        return sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+    }
+    let tiny = 1e-300;
+    let mut b = x + 1.0 - a;
+    let (mut c, mut d) = (1.0 / tiny, 1.0 / b);
+    let mut h = d;
+    for i in 1..terms(a.max(x)) {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < tiny { d = tiny }
+        c = b + an / c;
+        if c.abs() < tiny { c = tiny }
+        d = 1.0 / d;
+        h *= d * c;
+        if (d * c - 1.0).abs() < 1e-16 { break }
+    }
+    1.0 - (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    let tiny = 1e-300;
+    let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < tiny { d = tiny }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..terms(a + b) {
+        let m = m as f64;
+        let aa = m * (b - m) * x / ((qam + 2.0 * m) * (a + 2.0 * m));
+        d = 1.0 + aa * d;
+        if d.abs() < tiny { d = tiny }
+        c = 1.0 + aa / c;
+        if c.abs() < tiny { c = tiny }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + 2.0 * m) * (qap + 2.0 * m));
+        d = 1.0 + aa * d;
+        if d.abs() < tiny { d = tiny }
+        c = 1.0 + aa / c;
+        if c.abs() < tiny { c = tiny }
+        d = 1.0 / d;
+        let del = d * c;
+        h *= del;
+        if (del - 1.0).abs() < 1e-16 { break }
+    }
+    h
+}
+pub fn beta_inc(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 { return 0.0 }
+    if x >= 1.0 { return 1.0 }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) { front * beta_cf(a, b, x) / a } else { 1.0 - front * beta_cf(b, a, 1.0 - x) / b }
+}
+pub fn erf(x: f64) -> f64 {
+    let p = gamma_p(0.5, x * x);
+    if x < 0.0 { -p } else { p }
+}
+
+// -- DISTRIBUTIONS --------------------------------------------------------------------
+
+fn norm_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / 2f64.sqrt()))
+}
+fn norm_inv(p: f64) -> f64 {
+    if p <= 0.0 { return f64::NEG_INFINITY }
+    if p >= 1.0 { return f64::INFINITY }
+    let a = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2, 1.38357751867269e2, -3.066479806614716e1, 2.506628277459239];
+    let b = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2, 6.680131188771972e1, -1.328068155288572e1];
+    let c = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838, -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    let d = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    let tail = |q: f64| (((((c[0] * q + c[1]) * q + c[2]) * q + c[3]) * q + c[4]) * q + c[5])
+        / ((((d[0] * q + d[1]) * q + d[2]) * q + d[3]) * q + 1.0);
+    let mut x = if p < 0.02425 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.02425 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((a[0] * r + a[1]) * r + a[2]) * r + a[3]) * r + a[4]) * r + a[5]) * q
+        / (((((b[0] * r + b[1]) * r + b[2]) * r + b[3]) * r + b[4]) * r + 1.0)
+    };
+    let e = norm_cdf(x) - p;
+    let u = e * (2.0 * PI).sqrt() * (x * x / 2.0).exp();
+    x -= u / (1.0 + x * u / 2.0);
+    x
+}
+fn t_pdf(x: f64, v: f64) -> f64 {
+    (ln_gamma((v + 1.0) / 2.0) - ln_gamma(v / 2.0) - 0.5 * (v * PI).ln() - (v + 1.0) / 2.0 * (1.0 + x * x / v).ln()).exp()
+}
+fn t_cdf(x: f64, v: f64) -> f64 {
+    let tail = 0.5 * beta_inc(v / 2.0, 0.5, v / (v + x * x));
+    if x >= 0.0 { 1.0 - tail } else { tail }
+}
+fn t_inv(p: f64, v: f64) -> f64 {
+    if p <= 0.0 { return f64::NEG_INFINITY }
+    if p >= 1.0 { return f64::INFINITY }
+    if p < 0.5 { return -t_inv(1.0 - p, v) }
+    let (mut lo, mut hi) = (0.0, 1.0);
+    while t_cdf(hi, v) < p { hi *= 2.0 }
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        if t_cdf(mid, v) < p { lo = mid } else { hi = mid }
+    }
+    lo
+}
+fn binom_pdf(k: f64, n: f64, p: f64) -> f64 {
+    if k < 0.0 || k > n || k.fract() != 0.0 { return 0.0 }
+    if p == 0.0 { return (k == 0.0) as i64 as f64 }
+    if p == 1.0 { return (k == n) as i64 as f64 }
+    (ln_gamma(n + 1.0) - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0) + k * p.ln() + (n - k) * (1.0 - p).ln()).exp()
+}
+fn binom_cdf(k: f64, n: f64, p: f64) -> f64 {
+    let k = k.floor();
+    if k < 0.0 { return 0.0 }
+    if k >= n { return 1.0 }
+    beta_inc(n - k, k + 1.0, 1.0 - p)
+}
+fn poiss_pdf(k: f64, lambda: f64) -> f64 {
+    if k < 0.0 || k.fract() != 0.0 { return 0.0 }
+    if lambda == 0.0 { return (k == 0.0) as i64 as f64 }
+    (k * lambda.ln() - lambda - ln_gamma(k + 1.0)).exp()
+}
+fn poiss_cdf(k: f64, lambda: f64) -> f64 {
+    let k = k.floor();
+    if k < 0.0 { return 0.0 }
+    1.0 - gamma_p(k + 1.0, lambda)
+}
+// the smallest k in 0..=max with cdf(k) >= p, bracketed around the normal approximation and then bisected
+fn discrete_inv(p: f64, mean: f64, sd: f64, max: f64, cdf: &dyn Fn(f64) -> f64) -> f64 {
+    let p = p - 1e-12;
+    if max.is_finite() && cdf(max) < p { return f64::INFINITY }
+    let guess = (mean + sd * norm_inv(p.clamp(1e-300, 1.0))).round().clamp(0.0, max);
+    let (mut lo, mut hi) = (guess - 1.0, guess);
+    let mut width = sd.max(1.0).ceil();
+    while cdf(hi) < p {
+        lo = hi;
+        hi = (hi + width).min(max);
+        width *= 2.0;
+    }
+    while lo >= 0.0 && cdf(lo) >= p {
+        hi = lo;
+        lo = (lo - width).max(-1.0);
+        width *= 2.0;
+    }
+    // cdf(lo) < p <= cdf(hi), with lo = -1 standing for below the support
+    while hi - lo > 1.0 {
+        let mid = ((lo + hi) / 2.0).floor();
+        if cdf(mid) >= p { hi = mid } else { lo = mid }
+    }
+    hi
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+type Density = dyn Fn(f64, &[f64]) -> Result<f64, String>;
+
+fn distribution(name: &str, args: &[(Value, Position)], params: usize, pos: &Position, file_path: &str,
+    func: &Density) -> Result<Value, Error> {
+    expect_args(name, args, params + 1, pos, file_path)?;
+    reject_duals(name, args, file_path)?;
+    let mut values: Vec<f64> = vec![];
+    for arg in &args[1..] { values.push(expect_float(name, arg, file_path)?) }
+    let apply = |x: f64| func(x, &values).map(Value::Float)
+        .map_err(|detail| Error::Domain(format!("{name} expects {detail}"), pos.clone(), file_path.to_string()));
+    match &args[0].0 {
+        Value::Vector(vector) => {
+            let mut result: Vec<Value> = vec![];
+            for value in vector {
+                let x = value.number().ok_or_else(|| illegal_arg(name, &args[0], file_path))?;
+                result.push(apply(x)?);
+            }
+            Ok(Value::Vector(result))
+        }
+        _ => apply(expect_float(name, &args[0], file_path)?)
+    }
+}
+fn check(valid: bool, detail: &str) -> Result<(), String> {
+    if valid { Ok(()) } else { Err(detail.to_string()) }
+}
+fn check_p(p: f64) -> Result<(), String> {
+    check((0.0..=1.0).contains(&p), "a probability between 0 and 1")
+}
+fn check_n(n: f64) -> Result<(), String> {
+    check(n >= 0.0 && n.fract() == 0.0, "a non-negative integer number of trials")
+}
+
+pub fn normpdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("normpdf", args, 2, pos, file_path, &|x, p| {
+        check(p[1] > 0.0, "a positive standard deviation")?;
+        let z = (x - p[0]) / p[1];
+        Ok((-z * z / 2.0).exp() / (p[1] * (2.0 * PI).sqrt()))
+    })
+}
+pub fn normcdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("normcdf", args, 2, pos, file_path, &|x, p| {
+        check(p[1] > 0.0, "a positive standard deviation")?;
+        Ok(norm_cdf((x - p[0]) / p[1]))
+    })
+}
+pub fn norminv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("norminv", args, 2, pos, file_path, &|q, p| {
+        check(p[1] > 0.0, "a positive standard deviation")?;
+        check_p(q)?;
+        Ok(p[0] + p[1] * norm_inv(q))
+    })
+}
+pub fn unifpdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("unifpdf", args, 2, pos, file_path, &|x, p| {
+        check(p[0] < p[1], "a lower bound below the upper bound")?;
+        Ok(if x >= p[0] && x <= p[1] { 1.0 / (p[1] - p[0]) } else { 0.0 })
+    })
+}
+pub fn unifcdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("unifcdf", args, 2, pos, file_path, &|x, p| {
+        check(p[0] < p[1], "a lower bound below the upper bound")?;
+        Ok(((x - p[0]) / (p[1] - p[0])).clamp(0.0, 1.0))
+    })
+}
+pub fn unifinv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("unifinv", args, 2, pos, file_path, &|q, p| {
+        check(p[0] < p[1], "a lower bound below the upper bound")?;
+        check_p(q)?;
+        Ok(p[0] + q * (p[1] - p[0]))
+    })
+}
+pub fn binompdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("binompdf", args, 2, pos, file_path, &|k, p| {
+        check_n(p[0])?;
+        check_p(p[1])?;
+        Ok(binom_pdf(k, p[0], p[1]))
+    })
+}
+pub fn binomcdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("binomcdf", args, 2, pos, file_path, &|k, p| {
+        check_n(p[0])?;
+        check_p(p[1])?;
+        Ok(binom_cdf(k, p[0], p[1]))
+    })
+}
+pub fn binominv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("binominv", args, 2, pos, file_path, &|q, p| {
+        check_n(p[0])?;
+        check_p(p[1])?;
+        check_p(q)?;
+        Ok(discrete_inv(q, p[0] * p[1], (p[0] * p[1] * (1.0 - p[1])).sqrt(), p[0], &|k| binom_cdf(k, p[0], p[1])))
+    })
+}
+pub fn poisspdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("poisspdf", args, 1, pos, file_path, &|k, p| {
+        check(p[0] >= 0.0, "a non-negative rate")?;
+        Ok(poiss_pdf(k, p[0]))
+    })
+}
+pub fn poisscdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("poisscdf", args, 1, pos, file_path, &|k, p| {
+        check(p[0] >= 0.0, "a non-negative rate")?;
+        Ok(poiss_cdf(k, p[0]))
+    })
+}
+pub fn poissinv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("poissinv", args, 1, pos, file_path, &|q, p| {
+        check(p[0] >= 0.0, "a non-negative rate")?;
+        check_p(q)?;
+        if q == 1.0 { return Ok(f64::INFINITY) }
+        Ok(discrete_inv(q, p[0], p[0].sqrt(), f64::INFINITY, &|k| poiss_cdf(k, p[0])))
+    })
+}
+pub fn exppdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("exppdf", args, 1, pos, file_path, &|x, p| {
+        check(p[0] > 0.0, "a positive rate")?;
+        Ok(if x < 0.0 { 0.0 } else { p[0] * (-p[0] * x).exp() })
+    })
+}
+pub fn expcdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("expcdf", args, 1, pos, file_path, &|x, p| {
+        check(p[0] > 0.0, "a positive rate")?;
+        Ok(if x < 0.0 { 0.0 } else { 1.0 - (-p[0] * x).exp() })
+    })
+}
+pub fn expinv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("expinv", args, 1, pos, file_path, &|q, p| {
+        check(p[0] > 0.0, "a positive rate")?;
+        check_p(q)?;
+        Ok(-(1.0 - q).ln() / p[0])
+    })
+}
+pub fn tpdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("tpdf", args, 1, pos, file_path, &|x, p| {
+        check(p[0] > 0.0, "positive degrees of freedom")?;
+        Ok(t_pdf(x, p[0]))
+    })
+}
+pub fn tcdf(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("tcdf", args, 1, pos, file_path, &|x, p| {
+        check(p[0] > 0.0, "positive degrees of freedom")?;
+        Ok(t_cdf(x, p[0]))
+    })
+}
+pub fn tinv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    distribution("tinv", args, 1, pos, file_path, &|q, p| {
+        check(p[0] > 0.0, "positive degrees of freedom")?;
+        check_p(q)?;
+        Ok(t_inv(q, p[0]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9 * (1.0 + b.abs())
+    }
+
+    #[test]
+    fn special_functions() {
+        assert!(close(ln_gamma(5.0), 24f64.ln()));
+        assert!(close(ln_gamma(0.5), PI.sqrt().ln()));
+        assert!(close(erf(1.0), 0.842_700_792_949_714_9));
+        assert!(close(beta_inc(2.0, 3.0, 0.4), 0.5248));
+    }
+    #[test]
+    fn continuous_distributions() {
+        assert!(close(norm_cdf(1.959_963_984_540_054), 0.975));
+        assert!(close(norm_inv(0.975), 1.959_963_984_540_054));
+        assert!(close(t_cdf(2.0, 5.0), 0.949_030_260_585_070_8));
+        assert!(close(t_inv(0.975, 10.0), 2.228_138_851_986_273_4));
+        assert_eq!(eval_lines(&["unifcdf(0.25 0 1)"]).unwrap().to_string(), "0.25");
+        assert!(close(eval_lines(&["expinv(0.5 2)"]).unwrap().number().unwrap(), 2f64.ln() / 2.0));
+        assert!(eval_lines(&["normpdf(0 0 0)"]).is_err());
+    }
+    #[test]
+    fn discrete_distributions() {
+        assert!(close(binom_pdf(3.0, 10.0, 0.5), 120.0 / 1024.0));
+        assert!(close(binom_cdf(3.0, 10.0, 0.5), 176.0 / 1024.0));
+        assert!(close(poiss_pdf(2.0, 3.0), 4.5 * (-3f64).exp()));
+        assert!(close(poiss_cdf(2.0, 3.0), 8.5 * (-3f64).exp()));
+        assert_eq!(eval_lines(&["binominv([0 0.1 0.5 1] 20 0.3)"]).unwrap().to_string(), "[0, 3, 6, 20]");
+        assert_eq!(eval_lines(&["poissinv([0 0.5 1] 3)"]).unwrap().to_string(), "[0, 3, inf]");
+    }
+    #[test]
+    fn inverses_of_large_discrete_distributions_bisect() {
+        assert_eq!(eval_lines(&["poissinv(0.5 10^8)"]).unwrap().to_string(), "100000000");
+        assert_eq!(eval_lines(&["binominv(0.5 10^9 0.5)"]).unwrap().to_string(), "500000000");
+        let k = eval_lines(&["poissinv(0.975 10^6)"]).unwrap().number().unwrap();
+        assert!(poiss_cdf(k, 1e6) >= 0.975 && poiss_cdf(k - 1.0, 1e6) < 0.975);
+    }
+}
\ No newline at end of file
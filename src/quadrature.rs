@@ -0,0 +1,113 @@
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::dual::*;
+
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6, 0.949_107_912_342_758_5, 0.864_864_423_359_769_1, 0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1, 0.405_845_151_377_397_2, 0.207_784_955_007_898_5, 0.0
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_225, 0.063_092_092_629_978_55, 0.104_790_010_322_250_18, 0.140_653_259_715_525_92,
+    0.169_004_726_639_267_9, 0.190_350_578_064_785_4, 0.204_432_940_075_298_9, 0.209_482_141_084_727_83
+];
+const GAUSS_WEIGHTS: [f64; 4] = [0.129_484_966_168_869_7, 0.279_705_391_489_276_7, 0.381_830_050_505_118_9, 0.417_959_183_673_469_4];
+
+type Integrand<'a> = dyn FnMut(f64) -> Result<f64, Error> + 'a;
+
+// -- QUADRATURE -----------------------------------------------------------------------
+
+fn kronrod(f: &mut Integrand, a: f64, b: f64) -> Result<(f64, f64), Error> {
+    let (center, half) = ((a + b) / 2.0, (b - a) / 2.0);
+    let (mut kronrod, mut gauss) = (0.0, 0.0);
+    for (i, (node, weight)) in KRONROD_NODES.iter().zip(KRONROD_WEIGHTS.iter()).enumerate() {
+        let y = if *node == 0.0 { f(center)? } else { f(center - half * node)? + f(center + half * node)? };
+        kronrod += weight * y;
+        if i % 2 == 1 { gauss += GAUSS_WEIGHTS[i / 2] * y }
+    }
+    Ok((kronrod * half, ((kronrod - gauss) * half).abs()))
+}
+pub fn adaptive(f: &mut Integrand, a: f64, b: f64, tol: f64) -> Result<Option<(f64, f64)>, Error> {
+    let (value, error) = kronrod(f, a, b)?;
+    let mut intervals: Vec<(f64, f64, f64, f64)> = vec![(a, b, value, error)];
+    for _ in 0..2000 {
+        let total: f64 = intervals.iter().map(|interval| interval.2).sum();
+        let error: f64 = intervals.iter().map(|interval| interval.3).sum();
+        if !total.is_finite() || !error.is_finite() { return Ok(None) }
+        if error <= tol * total.abs().max(1.0) { return Ok(Some((total, error))) }
+        let worst = (0..intervals.len()).max_by(|i, j| intervals[*i].3.total_cmp(&intervals[*j].3)).unwrap_or(0);
+        let (a, b, _, _) = intervals.swap_remove(worst);
+        let mid = (a + b) / 2.0;
+        for (a, b) in [(a, mid), (mid, b)] {
+            let (value, error) = kronrod(f, a, b)?;
+            intervals.push((a, b, value, error));
+        }
+    }
+    Ok(None)
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+pub fn integrate(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    if args.len() != 3 && args.len() != 4 {
+        return Err(Error::ArgumentCount("integrate".to_string(), 3, args.len(), pos.clone(), file_path.to_string()))
+    }
+    reject_duals("integrate", args, file_path)?;
+    let func = expect_callable("integrate", &args[0], file_path)?;
+    let a = expect_float("integrate", &args[1], file_path)?;
+    let b = expect_float("integrate", &args[2], file_path)?;
+    let tol = match args.get(3) {
+        Some(arg) => expect_float("integrate", arg, file_path)?,
+        None => 1e-10
+    };
+    if tol <= 0.0 { return Err(Error::Domain(format!("integrate expects a positive tolerance, got {tol}"), args[3].1.clone(), file_path.to_string())) }
+    if a.is_nan() || b.is_nan() { return Err(illegal_arg("integrate", &args[1], file_path)) }
+    if a == b { return Ok(Value::Vector(vec![Value::Float(0.0), Value::Float(0.0)])) }
+    let (lo, hi, sign) = if a < b { (a, b, 1.0) } else { (b, a, -1.0) };
+    let mut eval = |x: f64| -> Result<f64, Error> {
+        if !x.is_finite() { return Ok(0.0) }
+        let value = call(&func, None, &[(Value::Float(x), pos.clone())], pos, file_path, context)?;
+        value.number().ok_or_else(|| Error::IllegalValue(value, Type::Builtin("integrate".to_string()), args[0].1.clone(), file_path.to_string()))
+    };
+    let result = match (lo.is_finite(), hi.is_finite()) {
+        (true, true) => adaptive(&mut eval, lo, hi, tol)?,
+        (true, false) => adaptive(&mut |t: f64| Ok(eval(lo + t / (1.0 - t))? / (1.0 - t).powi(2)), 0.0, 1.0, tol)?,
+        (false, true) => adaptive(&mut |t: f64| Ok(eval(hi - (1.0 - t) / t)? / t.powi(2)), 0.0, 1.0, tol)?,
+        (false, false) => adaptive(&mut |t: f64| Ok(eval(t / (1.0 - t * t))? * (1.0 + t * t) / (1.0 - t * t).powi(2)), -1.0, 1.0, tol)?
+    };
+    match result {
+        Some((value, error)) => Ok(Value::Vector(vec![Value::Float(sign * value), Value::Float(error)])),
+        None => Err(Error::Converge(format!("integrate from {a} to {b}"), pos.clone(), file_path.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use super::*;
+
+    #[test]
+    fn kronrod_is_exact_for_low_degree_polynomials() {
+        let (value, error) = kronrod(&mut |x| Ok(x.powi(9) - 3.0 * x * x + 1.0), 0.0, 2.0).unwrap();
+        assert!((value - (102.4 - 8.0 + 2.0)).abs() < 1e-12);
+        assert!(error < 1e-12);
+    }
+    #[test]
+    fn adaptive_refines_singular_integrands() {
+        let (value, error) = adaptive(&mut |x| Ok(1.0 / x.sqrt()), 0.0, 1.0, 1e-10).unwrap().unwrap();
+        assert!((value - 2.0).abs() < 1e-8 && error < 1e-8);
+        assert_eq!(adaptive(&mut |x| Ok(1.0 / x), 0.0, 1.0, 1e-10).unwrap(), None);
+    }
+    #[test]
+    fn integrate_maps_infinite_and_reversed_bounds() {
+        let value = |lines: &[&str]| match eval_lines(lines).unwrap() {
+            Value::Vector(result) => result[0].number().unwrap(),
+            value => panic!("expected a vector, got {value}")
+        };
+        assert!((value(&["g(x) : exp(0 - x^2)", "integrate(g 0-inf inf)"]) - PI.sqrt()).abs() < 1e-9);
+        assert!((value(&["integrate(sin 1 0)"]) + (1.0 - 1f64.cos())).abs() < 1e-12);
+        assert!(eval_lines(&["h(x) : 1 / x", "integrate(h 0 1)"]).is_err());
+        assert!(eval_lines(&["integrate(sin 0 1 0)"]).is_err());
+    }
+}
\ No newline at end of file
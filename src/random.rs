@@ -0,0 +1,135 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+
+// -- GENERATOR ------------------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct Rng {
+    state: [u64; 4]
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut splitmix = || {
+            x = x.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        };
+        Self { state: [splitmix(), splitmix(), splitmix(), splitmix()] }
+    }
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+        Self::new(nanos)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    pub fn below(&mut self, n: u64) -> u64 {
+        if n == 0 { return self.next_u64() }
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone { return x % n }
+        }
+    }
+    pub fn normal(&mut self) -> f64 {
+        loop {
+            let (u, v) = (2.0 * self.next_f64() - 1.0, 2.0 * self.next_f64() - 1.0);
+            let s = u * u + v * v;
+            if s > 0.0 && s < 1.0 { return u * (-2.0 * s.ln() / s).sqrt() }
+        }
+    }
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn generate(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context,
+    draw: fn(&mut Rng) -> f64) -> Result<Value, Error> {
+    if args.is_empty() { return Ok(Value::Float(draw(&mut context.rng))) }
+    expect_args(name, args, 1, pos, file_path)?;
+    let count = expect_int(name, &args[0], file_path)?;
+    if count < 0 { return Err(Error::Domain(
+        format!("{name} expects a non-negative count, got {count}"), args[0].1.clone(), file_path.to_string()
+    )) }
+    Ok(Value::Vector((0..count).map(|_| Value::Float(draw(&mut context.rng))).collect()))
+}
+pub fn seed(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("seed", args, 1, pos, file_path)?;
+    let seed = expect_int("seed", &args[0], file_path)?;
+    context.rng = Rng::new(seed as u64);
+    Ok(Value::Int(seed))
+}
+pub fn rand(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    generate("rand", args, pos, file_path, context, Rng::next_f64)
+}
+pub fn randn(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    generate("randn", args, pos, file_path, context, Rng::normal)
+}
+pub fn randint(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("randint", args, 2, pos, file_path)?;
+    let a = expect_int("randint", &args[0], file_path)?;
+    let b = expect_int("randint", &args[1], file_path)?;
+    if a > b { return Err(Error::Domain(
+        format!("randint expects a lower bound not above the upper bound, got {a} and {b}"), pos.clone(), file_path.to_string()
+    )) }
+    let span = (b as i128 - a as i128 + 1) as u128;
+    let offset = if span > u64::MAX as u128 { context.rng.next_u64() } else { context.rng.below(span as u64) };
+    Ok(Value::Int((a as i128 + offset as i128) as i64))
+}
+pub fn sample(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("sample", args, 2, pos, file_path)?;
+    let mut vector = expect_vector("sample", &args[0], file_path)?.clone();
+    let n = expect_int("sample", &args[1], file_path)?;
+    if n < 0 || n as usize > vector.len() { return Err(Error::Domain(
+        format!("sample expects a count between 0 and {}, got {n}", vector.len()), args[1].1.clone(), file_path.to_string()
+    )) }
+    for i in 0..n as usize {
+        let j = i + context.rng.below((vector.len() - i) as u64) as usize;
+        vector.swap(i, j);
+    }
+    vector.truncate(n as usize);
+    Ok(Value::Vector(vector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_seeds_give_equal_streams() {
+        let (mut a, mut b, mut c) = (Rng::new(42), Rng::new(42), Rng::new(43));
+        let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..5).map(|_| b.next_u64()).collect::<Vec<u64>>());
+        assert_ne!(first, (0..5).map(|_| c.next_u64()).collect::<Vec<u64>>());
+        let twice = |lines: &[&str]| eval_lines(lines).unwrap().to_string();
+        assert_eq!(twice(&["seed(42)", "[rand() randn() randint(1 6)]"]), twice(&["seed(42)", "[rand() randn() randint(1 6)]"]));
+        assert_eq!(twice(&["seed(42)", "rand()"]), "0.08386297105988216");
+    }
+    #[test]
+    fn draws_stay_in_range() {
+        let mut rng = Rng::new(1);
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.next_f64())));
+        assert!((0..1000).all(|_| rng.below(6) < 6));
+        let mean = (0..10000).map(|_| rng.normal()).sum::<f64>() / 10000.0;
+        assert!(mean.abs() < 0.05);
+        assert!(eval_lines(&["randint(3 1)"]).is_err());
+        assert!(eval_lines(&["sample([1 2] 3)"]).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,280 @@
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::parser::*;
+use crate::interpreter::*;
+use crate::symbolic::*;
+
+type Objective<'a> = dyn FnMut(f64) -> Result<f64, Error> + 'a;
+
+// -- ROOT FINDING ---------------------------------------------------------------------
+
+fn tolerance(x: f64) -> f64 {
+    1e-12 * (1.0 + x.abs())
+}
+pub fn newton(f: &mut Objective, x0: f64) -> Result<Option<f64>, Error> {
+    let mut x = x0;
+    let mut fx = f(x)?;
+    for _ in 0..100 {
+        if fx == 0.0 { return Ok(Some(x)) }
+        let h = 1e-7 * (1.0 + x.abs());
+        let slope = (f(x + h)? - f(x - h)?) / (2.0 * h);
+        if slope == 0.0 || !slope.is_finite() { return Ok(None) }
+        let mut step = fx / slope;
+        let last = x;
+        x = last - step;
+        fx = f(x)?;
+        // halve steps that land where the equation is undefined, like the pole of 1/x
+        for _ in 0..50 {
+            if fx.is_finite() { break }
+            step /= 2.0;
+            x = last - step;
+            fx = f(x)?;
+        }
+        if !fx.is_finite() { return Ok(None) }
+        if step.abs() <= tolerance(x) { return Ok((fx.abs() <= 1e-9 * (1.0 + slope.abs())).then_some(x)) }
+    }
+    Ok(None)
+}
+pub fn brent(f: &mut Objective, mut a: f64, mut b: f64, mut fa: f64, mut fb: f64) -> Result<f64, Error> {
+    if fa.abs() < fb.abs() { (a, b, fa, fb) = (b, a, fb, fa) }
+    let (mut c, mut fc, mut d) = (a, fa, b - a);
+    let mut bisected = true;
+    for _ in 0..200 {
+        if fb == 0.0 || (b - a).abs() <= tolerance(b) { return Ok(b) }
+        let mut s = if fa != fc && fb != fc {
+            a * fb * fc / ((fa - fb) * (fa - fc)) + b * fa * fc / ((fb - fa) * (fb - fc)) + c * fa * fb / ((fc - fa) * (fc - fb))
+        } else {
+            b - fb * (b - a) / (fb - fa)
+        };
+        let (lo, hi) = if (3.0 * a + b) / 4.0 < b { ((3.0 * a + b) / 4.0, b) } else { (b, (3.0 * a + b) / 4.0) };
+        if !(lo..=hi).contains(&s)
+        || (bisected && (s - b).abs() >= (b - c).abs() / 2.0)
+        || (!bisected && (s - b).abs() >= (c - d).abs() / 2.0) {
+            s = (a + b) / 2.0;
+            bisected = true;
+        } else {
+            bisected = false;
+        }
+        let fs = f(s)?;
+        d = c;
+        (c, fc) = (b, fb);
+        if fa * fs < 0.0 { (b, fb) = (s, fs) } else { (a, fa) = (s, fs) }
+        if fa.abs() < fb.abs() { (a, b, fa, fb) = (b, a, fb, fa) }
+    }
+    Ok(b)
+}
+fn crossing(f: &mut Objective, a: f64, b: f64, fa: f64, fb: f64) -> Result<Option<f64>, Error> {
+    if fa == 0.0 { return Ok(Some(a)) }
+    if fa.is_finite() && fb.is_finite() && fa * fb < 0.0 {
+        let root = brent(f, a, b, fa, fb)?;
+        if f(root)?.abs() <= 1e-6 * (1.0 + fa.abs().min(fb.abs())) { return Ok(Some(root)) }
+    }
+    Ok(None)
+}
+pub fn find_root(f: &mut Objective, x0: f64) -> Result<Option<f64>, Error> {
+    if let Some(root) = newton(f, x0)? { return Ok(Some(root)) }
+    // scan outwards on both sides at the same rate, checking each new interval next to the last one
+    let h = 0.1 * (1.0 + x0.abs());
+    let fx0 = f(x0)?;
+    let (mut left, mut right) = ((x0, fx0), (x0, fx0));
+    let mut width = h;
+    while width < 1e8 {
+        let mut found: Option<f64> = None;
+        for (side, sign) in [(&mut right, 1.0), (&mut left, -1.0)] {
+            let (a, fa) = *side;
+            let b = x0 + sign * width;
+            let fb = f(b)?;
+            if let Some(root) = crossing(f, a, b, fa, fb)? {
+                if found.is_none_or(|x| (root - x0).abs() < (x - x0).abs()) { found = Some(root) }
+            }
+            *side = (b, fb);
+        }
+        if found.is_some() { return Ok(found) }
+        width *= 2.0;
+    }
+    Ok(None)
+}
+pub fn roots_in(f: &mut Objective, a: f64, b: f64) -> Result<Vec<f64>, Error> {
+    let n = 400;
+    let xs: Vec<f64> = (0..=n).map(|i| a + (b - a) * i as f64 / n as f64).collect();
+    let mut ys: Vec<f64> = vec![];
+    for x in &xs { ys.push(f(*x)?) }
+    let mut roots: Vec<f64> = vec![];
+    let mut push = |root: f64| if roots.last().is_none_or(|last| (root - last).abs() > 1e-9 * (1.0 + root.abs())) { roots.push(root) };
+    for i in 0..n {
+        if let Some(root) = crossing(f, xs[i], xs[i + 1], ys[i], ys[i + 1])? { push(root); continue }
+        let local_min = i > 0 && ys[i].abs() <= ys[i - 1].abs() && ys[i].abs() <= ys[i + 1].abs() && ys[i] * ys[i + 1] > 0.0;
+        if local_min {
+            if let Some(root) = newton(f, xs[i])? {
+                if root >= xs[i - 1] && root <= xs[i + 1] { push(root) }
+            }
+        }
+    }
+    if ys[n] == 0.0 { push(xs[n]) }
+    roots.sort_by(|a, b| a.total_cmp(b));
+    Ok(roots)
+}
+
+// -- SOLVE ----------------------------------------------------------------------------
+
+fn numeric(value: Value, pos: &Position, file_path: &str) -> Result<f64, Error> {
+    value.number().ok_or_else(|| Error::IllegalValue(value, Type::Float, pos.clone(), file_path.to_string()))
+}
+fn undefined_as_nan(value: Result<f64, Error>) -> Result<f64, Error> {
+    match value {
+        Err(Error::Domain(_, _, _)) => Ok(f64::NAN),
+        value => value
+    }
+}
+fn difference(value: &Value, pos: &Position) -> Node {
+    match value {
+        Value::Expr(Node::Binary(Token::Equal, (left, _), (right, _))) => binary_node(Token::Subtract, left.as_ref().clone(), right.as_ref().clone(), pos),
+        Value::Expr(node) => node.clone(),
+        _ => Node::Int(0)
+    }
+}
+pub fn equation(value: &Value, var: &str, pos: &Position, file_path: &str) -> Result<Node, Error> {
+    let node = difference(value, pos);
+    if !contains(&node, var) { return Err(Error::Domain(
+        format!("solve expects an equation in {var}, got {value}"), pos.clone(), file_path.to_string()
+    )) }
+    let node = simplify_node(&node, pos);
+    if contains(&node, var) { return Ok(node) }
+    let message = if constant(&node) == Some(0.0) { "holds for every" } else { "has no solution for" };
+    Err(Error::Domain(format!("{value} {message} {var}"), pos.clone(), file_path.to_string()))
+}
+fn system(target: (&Node, &Position), unknowns: &[(Node, Position)], file_path: &str, scope: &mut Context) -> Result<Value, Error> {
+    let mut vars: Vec<String> = vec![];
+    for (node, node_pos) in unknowns {
+        let Node::Variable(var) = node else {
+            return Err(Error::ExpectNode(Node::Variable("".to_string()), node.clone(), node_pos.clone(), file_path.to_string()))
+        };
+        vars.push(var.clone());
+    }
+    scope.stack.retain(|(name, _)| !vars.contains(name));
+    let value = interpret(target, file_path, scope)?;
+    let Value::Vector(equations) = &value else {
+        return Err(Error::IllegalValue(value, Type::Vector, target.1.clone(), file_path.to_string()))
+    };
+    let nodes: Vec<Node> = equations.iter().map(|equation| difference(equation, target.1)).collect();
+    let solution = solve_linear_system(&nodes, &vars, target.1, file_path)?;
+    Ok(Value::Vector(solution.into_iter().map(expression).collect()))
+}
+pub fn solve(args: &[(Node, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    if args.is_empty() || args.len() > 3 {
+        return Err(Error::ArgumentCount("solve".to_string(), 2, args.len(), pos.clone(), file_path.to_string()))
+    }
+    let (target_node, target_pos) = &args[0];
+    let mut scope = context.clone();
+    scope.symbolic = true;
+    if let Some((Node::Vector(unknowns), _)) = args.get(1).filter(|(node, _)| matches!(node, Node::Vector(nodes) if nodes.iter().all(|(node, _)| matches!(node, Node::Variable(_))))) {
+        if args.len() > 2 {
+            return Err(Error::ArgumentCount("solve".to_string(), 2, args.len(), pos.clone(), file_path.to_string()))
+        }
+        let solution = system((target_node, target_pos), unknowns, file_path, &mut scope);
+        context.rng = scope.rng;
+        return solution
+    }
+    let mut target = interpret((target_node, target_pos), file_path, &mut scope)?;
+    let (unknown, guess) = match (&target, args.get(1)) {
+        (Value::Function(_, _) | Value::Builtin(_), guess) => {
+            if args.len() > 2 {
+                return Err(Error::ArgumentCount("solve".to_string(), 2, args.len(), pos.clone(), file_path.to_string()))
+            }
+            (None, guess)
+        }
+        (_, Some((Node::Variable(var), _))) => {
+            scope.stack.retain(|(name, _)| name != var);
+            target = interpret((target_node, target_pos), file_path, &mut scope)?;
+            (Some(var.clone()), args.get(2))
+        }
+        (Value::Expr(node), guess) => {
+            if args.len() > 2 { return Err(Error::ExpectNode(
+                Node::Variable("".to_string()), args[1].0.clone(), args[1].1.clone(), file_path.to_string()
+            )) }
+            match free_vars(node).as_slice() {
+                [var] => (Some(var.clone()), guess),
+                vars => return Err(Error::Domain(
+                    format!("solve expects an equation in one variable, got {}", vars.join(" ")), target_pos.clone(), file_path.to_string()
+                ))
+            }
+        }
+        _ => return Err(Error::IllegalValue(target, Type::Builtin("solve".to_string()), target_pos.clone(), file_path.to_string()))
+    };
+    context.rng = scope.rng;
+    if let (Some(var), None) = (&unknown, guess) {
+        if let Some(exact) = solve_polynomial(&equation(&target, var, target_pos, file_path)?, var, target_pos) { return Ok(exact) }
+    }
+    let guess = match guess {
+        Some((node, node_pos)) => (interpret((node, node_pos), file_path, context)?, node_pos.clone()),
+        None => (Value::Int(1), pos.clone())
+    };
+    let mut f: Box<Objective> = match &unknown {
+        None => Box::new(|x: f64| undefined_as_nan(
+            call(&target, None, &[(Value::Float(x), pos.clone())], pos, file_path, context)
+                .and_then(|value| numeric(value, target_pos, file_path))
+        )),
+        Some(var) => {
+            let node = equation(&target, var, target_pos, file_path)?;
+            let mut eval_scope = context.scope();
+            Box::new(move |x: f64| {
+                eval_scope.set(var, &Value::Float(x));
+                undefined_as_nan(interpret((&node, target_pos), file_path, &mut eval_scope)
+                    .and_then(|value| numeric(value, target_pos, file_path)))
+            })
+        }
+    };
+    match &guess.0 {
+        Value::Vector(bounds) => {
+            let [a, b] = bounds.as_slice() else { return Err(Error::Shape(2, bounds.len(), guess.1.clone(), file_path.to_string())) };
+            let (a, b) = (numeric(a.clone(), &guess.1, file_path)?, numeric(b.clone(), &guess.1, file_path)?);
+            let roots = roots_in(&mut f, a.min(b), a.max(b))?;
+            Ok(Value::Vector(roots.into_iter().map(Value::Float).collect()))
+        }
+        value => {
+            let x0 = numeric(value.clone(), &guess.1, file_path)?;
+            match find_root(&mut f, x0)? {
+                Some(root) => Ok(Value::Float(root)),
+                None => Err(Error::Converge(format!("solve from {x0}"), target_pos.clone(), file_path.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use super::*;
+
+    fn close(x: Option<f64>, expected: f64) -> bool {
+        x.is_some_and(|x| (x - expected).abs() < 1e-9)
+    }
+
+    #[test]
+    fn newton_halves_steps_that_leave_the_domain() {
+        assert!(close(newton(&mut |x| Ok(x * x - 2.0), 1.0).unwrap(), 2f64.sqrt()));
+        assert!(close(newton(&mut |x| Ok(x.sqrt() - 1.0), 9.0).unwrap(), 1.0));
+    }
+    #[test]
+    fn find_root_scans_both_sides_when_newton_fails() {
+        assert!(close(find_root(&mut |x| Ok(1.0 / x - 2.0), 1.0).unwrap(), 0.5));
+        assert!(close(find_root(&mut |x| Ok(x + 3.0), 0.0).unwrap(), -3.0));
+        assert_eq!(find_root(&mut |x| Ok(x * x + 1.0), 0.0).unwrap(), None);
+    }
+    #[test]
+    fn brent_and_roots_in_stay_in_the_interval() {
+        let f = |x: f64| Ok(x.cos() - x);
+        assert!((brent(&mut f.clone(), 0.0, 1.0, 1.0, 1f64.cos() - 1.0).unwrap() - 0.739_085_133_215_160_7).abs() < 1e-12);
+        let roots = roots_in(&mut |x| Ok(x.sin()), -1.0, 7.0).unwrap();
+        assert_eq!(roots.len(), 3);
+        assert!(roots.iter().zip([0.0, PI, 2.0 * PI]).all(|(x, root)| (x - root).abs() < 1e-9));
+    }
+    #[test]
+    fn solve_reports_identities_and_contradictions() {
+        assert_eq!(eval_lines(&["solve(1/x = 2 x)"]).unwrap().to_string(), "0.5");
+        let error = eval_lines(&["solve(x = x x)"]).unwrap_err();
+        assert!(error.to_string().starts_with("ERROR: x = x holds for every x"));
+        assert!(eval_lines(&["solve(x = x + 1 x)"]).unwrap_err().to_string().starts_with("ERROR: x = x + 1 has no solution for x"));
+    }
+}
\ No newline at end of file
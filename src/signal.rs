@@ -0,0 +1,183 @@
+use std::f64::consts::PI;
+use crate::position::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::complex::*;
+use crate::functional::*;
+
+const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+// -- TRANSFORMS -----------------------------------------------------------------------
+
+fn unit(angle: f64) -> Complex {
+    let z = Complex::polar(1.0, angle);
+    let snap = |x: f64| if x.abs() < 1e-15 { 0.0 } else { x };
+    Complex::new(snap(z.re), snap(z.im))
+}
+fn radix2(x: &mut [Complex], inverse: bool) {
+    let n = x.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 { j ^= bit; bit >>= 1 }
+        j |= bit;
+        if i < j { x.swap(i, j) }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let twiddles: Vec<Complex> = (0..len / 2).map(|k| unit(sign * 2.0 * PI * k as f64 / len as f64)).collect();
+        for start in (0..n).step_by(len) {
+            for (k, w) in twiddles.iter().enumerate() {
+                let (u, v) = (x[start + k], x[start + k + len / 2] * *w);
+                x[start + k] = u + v;
+                x[start + k + len / 2] = u - v;
+            }
+        }
+        len <<= 1;
+    }
+}
+fn transform(x: &[Complex], inverse: bool) -> Vec<Complex> {
+    let n = x.len();
+    if n.is_power_of_two() {
+        let mut y = x.to_vec();
+        radix2(&mut y, inverse);
+        return y
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    if n <= 32 {
+        return (0..n).map(|k| x.iter().enumerate()
+            .fold(ZERO, |acc, (j, x)| acc + *x * unit(sign * 2.0 * PI * ((j * k) % n) as f64 / n as f64))).collect()
+    }
+    // bluestein's algorithm writes the transform as a convolution with a chirp, done with power of two transforms
+    let chirp: Vec<Complex> = (0..n).map(|k| Complex::polar(1.0, sign * PI * ((k * k) % (2 * n)) as f64 / n as f64)).collect();
+    let m = (2 * n - 1).next_power_of_two();
+    let (mut a, mut b) = (vec![ZERO; m], vec![ZERO; m]);
+    for k in 0..n {
+        a[k] = x[k] * chirp[k];
+        b[k] = chirp[k].conj();
+        if k > 0 { b[m - k] = chirp[k].conj() }
+    }
+    radix2(&mut a, false);
+    radix2(&mut b, false);
+    let mut c: Vec<Complex> = a.iter().zip(&b).map(|(a, b)| *a * *b).collect();
+    radix2(&mut c, true);
+    (0..n).map(|k| c[k] * chirp[k] / Complex::new(m as f64, 0.0)).collect()
+}
+fn convolve(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    let mut y = vec![ZERO; a.len() + b.len() - 1];
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() { y[i + j] = y[i + j] + *a * *b }
+    }
+    y
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn expect_signal(name: &str, arg: &(Value, Position), file_path: &str) -> Result<(Vec<Complex>, bool), Error> {
+    let values = expect_vector(name, arg, file_path)?;
+    if values.is_empty() { return Err(Error::Empty(name.to_string(), arg.1.clone(), file_path.to_string())) }
+    let signal = values.iter().map(|value| match value {
+        Value::Vector(_) => None,
+        value => as_complex(value)
+    }).collect::<Option<Vec<Complex>>>().ok_or_else(|| illegal_arg(name, arg, file_path))?;
+    Ok((signal, values.iter().all(|value| !matches!(value, Value::Complex(_)))))
+}
+fn signal(values: Vec<Complex>, real: bool) -> Value {
+    let scale = values.iter().map(|z| z.norm()).fold(0.0, f64::max);
+    let chop = |x: f64| if x.abs() <= 1e-12 * scale { 0.0 } else { x + 0.0 };
+    Value::Vector(values.into_iter().map(|z| if real {
+        Value::Float(chop(z.re))
+    } else {
+        Value::Complex(Complex::new(chop(z.re), chop(z.im)))
+    }).collect())
+}
+fn window(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str, func: &dyn Fn(f64) -> f64) -> Result<Value, Error> {
+    expect_args(name, args, 1, pos, file_path)?;
+    let n = expect_int(name, &args[0], file_path)?;
+    if n < 1 { return Err(illegal_arg(name, &args[0], file_path)) }
+    if n == 1 { return Ok(Value::Vector(vec![Value::Float(1.0)])) }
+    Ok(Value::Vector((0..n).map(|k| Value::Float(match func(2.0 * PI * k as f64 / (n - 1) as f64) { w if w.abs() < 1e-12 => 0.0, w => w })).collect()))
+}
+pub fn fft(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("fft", args, 1, pos, file_path)?;
+    let (x, _) = expect_signal("fft", &args[0], file_path)?;
+    Ok(signal(transform(&x, false), false))
+}
+pub fn ifft(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("ifft", args, 1, pos, file_path)?;
+    let (x, _) = expect_signal("ifft", &args[0], file_path)?;
+    let n = Complex::new(x.len() as f64, 0.0);
+    Ok(signal(transform(&x, true).into_iter().map(|z| z / n).collect(), false))
+}
+pub fn conv(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("conv", args, 2, pos, file_path)?;
+    let (a, real_a) = expect_signal("conv", &args[0], file_path)?;
+    let (b, real_b) = expect_signal("conv", &args[1], file_path)?;
+    Ok(signal(convolve(&a, &b), real_a && real_b))
+}
+pub fn xcorr(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    if args.len() != 1 && args.len() != 2 {
+        return Err(Error::ArgumentCount("xcorr".to_string(), 2, args.len(), pos.clone(), file_path.to_string()))
+    }
+    let (a, real_a) = expect_signal("xcorr", &args[0], file_path)?;
+    let (b, real_b) = expect_signal("xcorr", args.last().unwrap_or(&args[0]), file_path)?;
+    let reversed: Vec<Complex> = b.iter().rev().map(|z| z.conj()).collect();
+    Ok(signal(convolve(&a, &reversed), real_a && real_b))
+}
+// filter(b a x) runs a signal through a linear filter, filter(pred v) keeps the elements a predicate accepts
+pub fn filter(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    if args.len() != 3 { return predicate_filter(args, pos, file_path, context) }
+    let (b, real_b) = expect_signal("filter", &args[0], file_path)?;
+    let (a, real_a) = expect_signal("filter", &args[1], file_path)?;
+    let (x, real_x) = expect_signal("filter", &args[2], file_path)?;
+    if a[0] == ZERO { return Err(Error::Domain("filter expects a nonzero leading denominator coefficient".to_string(), args[1].1.clone(), file_path.to_string())) }
+    let mut y: Vec<Complex> = vec![];
+    for n in 0..x.len() {
+        let mut sum = ZERO;
+        for (k, b) in b.iter().enumerate().take(n + 1) { sum = sum + *b * x[n - k] }
+        for (k, a) in a.iter().enumerate().take(n + 1).skip(1) { sum = sum - *a * y[n - k] }
+        y.push(sum / a[0]);
+    }
+    Ok(signal(y, real_a && real_b && real_x))
+}
+pub fn hann(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    window("hann", args, pos, file_path, &|t| 0.5 - 0.5 * t.cos())
+}
+pub fn hamming(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    window("hamming", args, pos, file_path, &|t| 0.54 - 0.46 * t.cos())
+}
+pub fn blackman(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    window("blackman", args, pos, file_path, &|t| 0.42 - 0.5 * t.cos() + 0.08 * (2.0 * t).cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direct(x: &[Complex]) -> Vec<Complex> {
+        let n = x.len();
+        (0..n).map(|k| x.iter().enumerate().fold(ZERO, |acc, (j, x)| acc + *x * Complex::polar(1.0, -2.0 * PI * (j * k) as f64 / n as f64))).collect()
+    }
+
+    #[test]
+    fn every_length_matches_the_direct_transform_and_round_trips() {
+        // powers of two use radix-2, short lengths the direct sum and longer ones bluestein
+        for n in [1, 8, 12, 50, 64, 97] {
+            let x: Vec<Complex> = (0..n).map(|k| Complex::new((k as f64 * 0.7).sin(), (k as f64 * 0.3).cos())).collect();
+            let y = transform(&x, false);
+            assert!(y.iter().zip(direct(&x)).all(|(a, b)| (*a - b).norm() < 1e-9), "fft of length {n}");
+            let back = transform(&y, true);
+            assert!(back.iter().zip(&x).all(|(a, b)| (*a / Complex::new(n as f64, 0.0) - *b).norm() < 1e-12), "ifft of length {n}");
+        }
+    }
+    #[test]
+    fn builtins_round_trip_and_filter_dispatches_on_arity() {
+        assert_eq!(eval_lines(&["fft([1 2 3 4])"]).unwrap().to_string(), "[10 + 0i, -2 + 2i, -2 + 0i, -2 - 2i]");
+        assert_eq!(eval_lines(&["ifft(fft([1 2 3 4]))"]).unwrap().to_string(), "[1 + 0i, 2 + 0i, 3 + 0i, 4 + 0i]");
+        assert_eq!(eval_lines(&["filter([1] [1 (-0.5)] [1 0 0 0])"]).unwrap().to_string(), "[1, 0.5, 0.25, 0.125]");
+        assert_eq!(eval_lines(&["big(x) : x > 2", "filter(big [1 2 3 4])"]).unwrap().to_string(), "[3, 4]");
+        assert!(eval_lines(&["filter([1] [0] [1 2])"]).is_err());
+    }
+}
\ No newline at end of file
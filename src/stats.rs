@@ -0,0 +1,269 @@
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::vector::MAX_RANGE;
+use crate::dual::*;
+
+pub fn expect_numbers(name: &str, arg: &(Value, Position), file_path: &str) -> Result<Vec<f64>, Error> {
+    let mut numbers: Vec<f64> = vec![];
+    for value in expect_vector(name, arg, file_path)? {
+        match value.number() {
+            Some(number) => numbers.push(number),
+            None => return Err(Error::IllegalValue(
+                value.clone(), Type::Builtin(name.to_string()), arg.1.clone(), file_path.to_string()
+            ))
+        }
+    }
+    Ok(numbers)
+}
+fn expect_reals(name: &str, arg: &(Value, Position), file_path: &str) -> Result<Vec<f64>, Error> {
+    expect_vector(name, arg, file_path)?.iter().map(|value| real(value).ok_or_else(|| Error::IllegalValue(
+        value.clone(), Type::Builtin(name.to_string()), arg.1.clone(), file_path.to_string()
+    ))).collect()
+}
+fn expect_size(name: &str, arg: &(Value, Position), len: usize, min: usize, file_path: &str) -> Result<(), Error> {
+    if len == 0 { return Err(Error::Empty(name.to_string(), arg.1.clone(), file_path.to_string())) }
+    if len < min { return Err(Error::Domain(
+        format!("{name} needs at least {min} values, got {len}"), arg.1.clone(), file_path.to_string()
+    )) }
+    Ok(())
+}
+fn expect_sample(name: &str, arg: &(Value, Position), min: usize, file_path: &str) -> Result<Vec<f64>, Error> {
+    let numbers = expect_numbers(name, arg, file_path)?;
+    expect_size(name, arg, numbers.len(), min, file_path)?;
+    Ok(numbers)
+}
+// a sample holding duals is promoted to duals so grad and hessian can see through the statistic
+fn expect_duals(name: &str, arg: &(Value, Position), min: usize, file_path: &str) -> Result<Option<Vec<Dual>>, Error> {
+    let vector = expect_vector(name, arg, file_path)?;
+    let Some(like) = vector.iter().find_map(|value| match value { Value::Dual(x) => Some(x), _ => None }) else { return Ok(None) };
+    let numbers = expect_reals(name, arg, file_path)?;
+    expect_size(name, arg, numbers.len(), min, file_path)?;
+    Ok(Some(vector.iter().zip(numbers).map(|(value, number)| match value {
+        Value::Dual(x) => x.clone(),
+        _ => Dual::constant(number, like)
+    }).collect()))
+}
+fn expect_pair(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str) -> Result<(Vec<f64>, Vec<f64>), Error> {
+    expect_args(name, args, 2, pos, file_path)?;
+    let x = expect_sample(name, &args[0], 2, file_path)?;
+    let y = expect_numbers(name, &args[1], file_path)?;
+    if x.len() != y.len() { return Err(Error::Shape(x.len(), y.len(), args[1].1.clone(), file_path.to_string())) }
+    Ok((x, y))
+}
+pub fn mean_of(numbers: &[f64]) -> f64 {
+    numbers.iter().sum::<f64>() / numbers.len() as f64
+}
+fn sum_squares(numbers: &[f64]) -> f64 {
+    let mean = mean_of(numbers);
+    numbers.iter().map(|x| (x - mean).powi(2)).sum()
+}
+fn covariance(x: &[f64], y: &[f64]) -> f64 {
+    let (mx, my) = (mean_of(x), mean_of(y));
+    x.iter().zip(y.iter()).map(|(x, y)| (x - mx) * (y - my)).sum::<f64>() / (x.len() - 1) as f64
+}
+fn quantile_of(sorted: &[f64], q: f64) -> f64 {
+    let h = (sorted.len() - 1) as f64 * q;
+    let (lo, hi) = (h.floor() as usize, h.ceil() as usize);
+    sorted[lo] + (h - lo as f64) * (sorted[hi] - sorted[lo])
+}
+fn sorted(mut numbers: Vec<f64>) -> Vec<f64> {
+    numbers.sort_by(|a, b| a.total_cmp(b));
+    numbers
+}
+fn dual_mean(duals: &[Dual]) -> Dual {
+    let total = duals[1..].iter().fold(duals[0].clone(), |total, x| total + x.clone());
+    total / Dual::constant(duals.len() as f64, &duals[0])
+}
+fn dual_sum_squares(duals: &[Dual]) -> Dual {
+    let mean = dual_mean(duals);
+    duals.iter().fold(Dual::constant(0.0, &mean), |total, x| {
+        let deviation = x.clone() - mean.clone();
+        total + deviation.clone() * deviation
+    })
+}
+fn dual_quantile(sorted: &[Dual], q: f64) -> Dual {
+    let h = (sorted.len() - 1) as f64 * q;
+    let (lo, hi) = (h.floor() as usize, h.ceil() as usize);
+    sorted[lo].clone() + Dual::constant(h - lo as f64, &sorted[lo]) * (sorted[hi].clone() - sorted[lo].clone())
+}
+fn dual_sorted(mut duals: Vec<Dual>) -> Vec<Dual> {
+    duals.sort_by(|a, b| a.re.total_cmp(&b.re));
+    duals
+}
+fn dual_spread(duals: &[Dual], ddof: usize, root: bool) -> Value {
+    let spread = dual_sum_squares(duals) / Dual::constant((duals.len() - ddof) as f64, &duals[0]);
+    Value::Dual(if root { spread.powf(0.5) } else { spread })
+}
+
+pub fn sum(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("sum", args, 1, pos, file_path)?;
+    expect_reals("sum", &args[0], file_path)?;
+    let mut total = Value::Int(0);
+    for value in expect_vector("sum", &args[0], file_path)? {
+        total = binary(&Token::Add, &total, value, pos, file_path)?;
+    }
+    Ok(total)
+}
+pub fn prod(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("prod", args, 1, pos, file_path)?;
+    expect_reals("prod", &args[0], file_path)?;
+    let mut total = Value::Int(1);
+    for value in expect_vector("prod", &args[0], file_path)? {
+        total = binary(&Token::Multiply, &total, value, pos, file_path)?;
+    }
+    Ok(total)
+}
+pub fn mean(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("mean", args, 1, pos, file_path)?;
+    if let Some(duals) = expect_duals("mean", &args[0], 1, file_path)? { return Ok(Value::Dual(dual_mean(&duals))) }
+    Ok(Value::Float(mean_of(&expect_sample("mean", &args[0], 1, file_path)?)))
+}
+pub fn median(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("median", args, 1, pos, file_path)?;
+    if let Some(duals) = expect_duals("median", &args[0], 1, file_path)? { return Ok(Value::Dual(dual_quantile(&dual_sorted(duals), 0.5))) }
+    Ok(Value::Float(quantile_of(&sorted(expect_sample("median", &args[0], 1, file_path)?), 0.5)))
+}
+pub fn mode(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("mode", args, 1, pos, file_path)?;
+    expect_sample("mode", &args[0], 1, file_path)?;
+    let vector = expect_vector("mode", &args[0], file_path)?;
+    let mut best = (&vector[0], 0);
+    for value in vector {
+        let count = vector.iter().filter(|v| v.number() == value.number()).count();
+        if count > best.1 || (count == best.1 && value.number() < best.0.number()) { best = (value, count) }
+    }
+    Ok(best.0.clone())
+}
+pub fn var(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("var", args, 1, pos, file_path)?;
+    if let Some(duals) = expect_duals("var", &args[0], 2, file_path)? { return Ok(dual_spread(&duals, 1, false)) }
+    let numbers = expect_sample("var", &args[0], 2, file_path)?;
+    Ok(Value::Float(sum_squares(&numbers) / (numbers.len() - 1) as f64))
+}
+pub fn varp(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("varp", args, 1, pos, file_path)?;
+    if let Some(duals) = expect_duals("varp", &args[0], 1, file_path)? { return Ok(dual_spread(&duals, 0, false)) }
+    let numbers = expect_sample("varp", &args[0], 1, file_path)?;
+    Ok(Value::Float(sum_squares(&numbers) / numbers.len() as f64))
+}
+pub fn std(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("std", args, 1, pos, file_path)?;
+    if let Some(duals) = expect_duals("std", &args[0], 2, file_path)? { return Ok(dual_spread(&duals, 1, true)) }
+    let numbers = expect_sample("std", &args[0], 2, file_path)?;
+    Ok(Value::Float((sum_squares(&numbers) / (numbers.len() - 1) as f64).sqrt()))
+}
+pub fn stdp(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("stdp", args, 1, pos, file_path)?;
+    if let Some(duals) = expect_duals("stdp", &args[0], 1, file_path)? { return Ok(dual_spread(&duals, 0, true)) }
+    let numbers = expect_sample("stdp", &args[0], 1, file_path)?;
+    Ok(Value::Float((sum_squares(&numbers) / numbers.len() as f64).sqrt()))
+}
+pub fn quantile(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("quantile", args, 2, pos, file_path)?;
+    let duals = expect_duals("quantile", &args[0], 1, file_path)?.map(dual_sorted);
+    let numbers = match &duals {
+        Some(duals) => duals.iter().map(|x| x.re).collect(),
+        None => sorted(expect_sample("quantile", &args[0], 1, file_path)?)
+    };
+    let q_of = |q: f64| {
+        if !(0.0..=1.0).contains(&q) { return Err(Error::Domain(
+            format!("quantile expects q between 0 and 1, got {q}"), args[1].1.clone(), file_path.to_string()
+        )) }
+        Ok(match &duals {
+            Some(duals) => Value::Dual(dual_quantile(duals, q)),
+            None => Value::Float(quantile_of(&numbers, q))
+        })
+    };
+    match &args[1].0 {
+        Value::Vector(_) => Ok(Value::Vector(
+            expect_numbers("quantile", &args[1], file_path)?.into_iter().map(q_of).collect::<Result<Vec<Value>, Error>>()?
+        )),
+        _ => q_of(expect_float("quantile", &args[1], file_path)?)
+    }
+}
+fn extreme(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str, greater: bool) -> Result<(usize, Value), Error> {
+    expect_args(name, args, 1, pos, file_path)?;
+    let numbers = expect_reals(name, &args[0], file_path)?;
+    if numbers.is_empty() { return Err(Error::Empty(name.to_string(), args[0].1.clone(), file_path.to_string())) }
+    let mut best = 0;
+    for (i, number) in numbers.iter().enumerate() {
+        if (greater && *number > numbers[best]) || (!greater && *number < numbers[best]) { best = i }
+    }
+    Ok((best, expect_vector(name, &args[0], file_path)?[best].clone()))
+}
+pub fn min(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    Ok(extreme("min", args, pos, file_path, false)?.1)
+}
+pub fn max(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    Ok(extreme("max", args, pos, file_path, true)?.1)
+}
+pub fn argmin(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    Ok(Value::Int(extreme("argmin", args, pos, file_path, false)?.0 as i64))
+}
+pub fn argmax(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    Ok(Value::Int(extreme("argmax", args, pos, file_path, true)?.0 as i64))
+}
+pub fn cov(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    let (x, y) = expect_pair("cov", args, pos, file_path)?;
+    Ok(Value::Float(covariance(&x, &y)))
+}
+pub fn corr(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    let (x, y) = expect_pair("corr", args, pos, file_path)?;
+    let (var_x, var_y) = (covariance(&x, &x), covariance(&y, &y));
+    if var_x == 0.0 || var_y == 0.0 { return Err(Error::Domain(
+        "corr is undefined for a sample with zero variance".to_string(), pos.clone(), file_path.to_string()
+    )) }
+    Ok(Value::Float(covariance(&x, &y) / (var_x * var_y).sqrt()))
+}
+pub fn histogram(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("histogram", args, 2, pos, file_path)?;
+    let numbers = expect_sample("histogram", &args[0], 1, file_path)?;
+    let edges = match &args[1].0 {
+        Value::Vector(_) => sorted(expect_numbers("histogram", &args[1], file_path)?),
+        _ => {
+            let bins = expect_int("histogram", &args[1], file_path)?;
+            if bins < 1 { return Err(Error::Domain(
+                format!("histogram expects a positive number of bins, got {bins}"), args[1].1.clone(), file_path.to_string()
+            )) }
+            if bins as f64 > MAX_RANGE { return Err(Error::Domain(
+                format!("histogram of {bins} bins is too large"), args[1].1.clone(), file_path.to_string()
+            )) }
+            let (lo, hi) = numbers.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| (lo.min(*x), hi.max(*x)));
+            let (lo, hi) = if lo == hi { (lo - 0.5, hi + 0.5) } else { (lo, hi) };
+            (0..=bins).map(|i| lo + (hi - lo) * i as f64 / bins as f64).collect()
+        }
+    };
+    if edges.len() < 2 { return Err(Error::Domain(
+        "histogram expects at least two bin edges".to_string(), args[1].1.clone(), file_path.to_string()
+    )) }
+    let mut counts = vec![0i64; edges.len() - 1];
+    for x in numbers {
+        if x < edges[0] || x > edges[edges.len() - 1] { continue }
+        let bin = edges[1..].iter().position(|edge| x < *edge).unwrap_or(counts.len() - 1);
+        counts[bin] += 1;
+    }
+    Ok(Value::Vector(counts.into_iter().map(Value::Int).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_of_a_sample() {
+        assert_eq!(quantile_of(&[1.0, 2.0, 3.0, 4.0], 0.25), 1.75);
+        assert_eq!(sum_squares(&[1.0, 2.0, 3.0]), 2.0);
+        assert_eq!(eval_lines(&["mode([1 2 2 3])"]).unwrap().to_string(), "2");
+        assert!(eval_lines(&["var([1])"]).unwrap_err().to_string().starts_with("ERROR: var needs at least 2 values"));
+    }
+    #[test]
+    fn degenerate_samples_are_defined_or_rejected() {
+        assert!(matches!(eval_lines(&["corr([1 1 1] [1 2 3])"]), Err(Error::Domain(_, _, _))));
+        assert_eq!(eval_lines(&["histogram([1 1 1] 2)"]).unwrap().to_string(), "[0, 3]");
+        assert_eq!(eval_lines(&["histogram([1 2 2 3] 2)"]).unwrap().to_string(), "[1, 3]");
+        assert!(matches!(eval_lines(&["histogram([1 2] 10^9)"]), Err(Error::Domain(_, _, _))));
+    }
+}
\ No newline at end of file
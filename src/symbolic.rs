@@ -0,0 +1,1057 @@
+use std::cell::RefCell;
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::parser::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::complex::*;
+
+// -- CONSTRUCTORS ---------------------------------------------------------------------
+
+pub fn constant(node: &Node) -> Option<f64> {
+    match node {
+        Node::Int(int) => Some(*int as f64),
+        Node::Float(float) => Some(*float),
+        _ => None
+    }
+}
+fn is(node: &Node, number: f64) -> bool {
+    constant(node) == Some(number)
+}
+pub fn number_node(number: f64) -> Node {
+    let number = number + 0.0;
+    if number.fract() == 0.0 && number.abs() < 1e15 { return Node::Int(number as i64) }
+    Node::Float(number)
+}
+pub fn poly_node(coeffs: &[f64], var: &str, pos: &Position) -> Node {
+    let mut node: Option<Node> = None;
+    for (power, coeff) in coeffs.iter().enumerate() {
+        if *coeff == 0.0 && coeffs.len() > 1 { continue }
+        let mut term = number_node(*coeff);
+        if power > 0 {
+            let mut x = Node::Variable(var.to_string());
+            if power > 1 {
+                x = Node::Binary(Token::Power, (Box::new(x), pos.clone()), (Box::new(Node::Int(power as i64)), pos.clone()));
+            }
+            term = Node::Binary(Token::Multiply, (Box::new(term), pos.clone()), (Box::new(x), pos.clone()));
+        }
+        node = Some(match node {
+            Some(node) => Node::Binary(Token::Add, (Box::new(node), pos.clone()), (Box::new(term), pos.clone())),
+            None => term
+        });
+    }
+    node.unwrap_or(Node::Int(0))
+}
+pub fn binary_node(op: Token, left: Node, right: Node, pos: &Position) -> Node {
+    Node::Binary(op, (Box::new(left), pos.clone()), (Box::new(right), pos.clone()))
+}
+pub fn add(left: Node, right: Node, pos: &Position) -> Node {
+    if let (Some(l), Some(r)) = (constant(&left), constant(&right)) { return number_node(l + r) }
+    if is(&left, 0.0) { return right }
+    if is(&right, 0.0) { return left }
+    binary_node(Token::Add, left, right, pos)
+}
+pub fn sub(left: Node, right: Node, pos: &Position) -> Node {
+    if let (Some(l), Some(r)) = (constant(&left), constant(&right)) { return number_node(l - r) }
+    if is(&left, 0.0) { return neg(right, pos) }
+    if is(&right, 0.0) { return left }
+    binary_node(Token::Subtract, left, right, pos)
+}
+pub fn mul(left: Node, right: Node, pos: &Position) -> Node {
+    if let (Some(l), Some(r)) = (constant(&left), constant(&right)) { return number_node(l * r) }
+    if is(&left, 0.0) || is(&right, 0.0) { return Node::Int(0) }
+    if is(&left, 1.0) { return right }
+    if is(&right, 1.0) { return left }
+    if is(&left, -1.0) { return neg(right, pos) }
+    if is(&right, -1.0) { return neg(left, pos) }
+    binary_node(Token::Multiply, left, right, pos)
+}
+pub fn div(left: Node, right: Node, pos: &Position) -> Node {
+    if let (Some(l), Some(r)) = (constant(&left), constant(&right)) {
+        if r != 0.0 && (l / r).fract() == 0.0 { return number_node(l / r) }
+    }
+    if is(&left, 0.0) && constant(&right).is_some_and(|r| r != 0.0) { return Node::Int(0) }
+    if is(&right, 1.0) { return left }
+    binary_node(Token::Divide, left, right, pos)
+}
+pub fn pow(left: Node, right: Node, pos: &Position) -> Node {
+    if let (Some(l), Some(r)) = (constant(&left), constant(&right)) {
+        if r >= 0.0 && r.fract() == 0.0 { return number_node(l.powf(r)) }
+    }
+    if is(&right, 0.0) { return Node::Int(1) }
+    if is(&right, 1.0) { return left }
+    binary_node(Token::Power, left, right, pos)
+}
+pub fn neg(node: Node, pos: &Position) -> Node {
+    if let Some(number) = constant(&node) { return number_node(-number) }
+    if let Node::Unary(Token::Subtract, (node, _)) = node { return *node }
+    Node::Unary(Token::Subtract, (Box::new(node), pos.clone()))
+}
+pub fn apply(name: &str, arg: Node, pos: &Position) -> Node {
+    Node::Call((Box::new(Node::Variable(name.to_string())), pos.clone()), vec![(arg, pos.clone())])
+}
+pub fn any_node(node: &Node, pred: &dyn Fn(&Node) -> bool) -> bool {
+    if pred(node) { return true }
+    let inside = |(node, _): &(Box<Node>, Position)| any_node(node, pred);
+    match node {
+        Node::Binary(_, left, right) | Node::Set(left, right) => inside(left) || inside(right),
+        Node::Unary(_, node) | Node::Diff(node, _) => inside(node),
+        Node::Vector(nodes) => nodes.iter().any(|(node, _)| any_node(node, pred)),
+        Node::Call(func, args) => inside(func) || args.iter().any(|(node, _)| any_node(node, pred)),
+        Node::Solve(args) | Node::Limit(_, args) => args.iter().any(|(node, _)| any_node(node, pred)),
+        Node::Range(start, end, step) => inside(start) || inside(end) || step.as_ref().is_some_and(inside),
+        Node::Series(_, _, values, body) => inside(values) || inside(body),
+        _ => false
+    }
+}
+pub fn contains(node: &Node, var: &str) -> bool {
+    let inside = |(node, _): &(Box<Node>, Position)| contains(node, var);
+    match node {
+        Node::Variable(name) => name == var,
+        Node::Binary(_, left, right) | Node::Set(left, right) => inside(left) || inside(right),
+        Node::Unary(_, node) | Node::Diff(node, _) => inside(node),
+        Node::Vector(nodes) => nodes.iter().any(|(node, _)| contains(node, var)),
+        Node::Call(func, args) => inside(func) || args.iter().any(|(node, _)| contains(node, var)),
+        Node::Solve(args) | Node::Limit(_, args) => args.iter().any(|(node, _)| contains(node, var)),
+        Node::Range(start, end, step) => inside(start) || inside(end) || step.as_ref().is_some_and(inside),
+        Node::Series(_, bound, values, body) => inside(values) || (bound != var && inside(body)),
+        _ => false
+    }
+}
+pub fn inline(params: &[String], body: &Node, args: &[(Node, Position)]) -> Node {
+    let mut body = body.clone();
+    for (i, param) in params.iter().enumerate() {
+        body = body.substitute(param, &Node::Variable(format!("{param}'{i}")));
+    }
+    for (i, (param, (arg, _))) in params.iter().zip(args.iter()).enumerate() {
+        body = body.substitute(&format!("{param}'{i}"), arg);
+    }
+    body
+}
+
+// -- EXPRESSIONS ----------------------------------------------------------------------
+
+pub fn lift(value: &Value, pos: &Position) -> Option<Node> {
+    match value {
+        Value::Int(int) => Some(Node::Int(*int)),
+        Value::Float(float) => Some(Node::Float(*float)),
+        Value::Bool(bool) => Some(Node::Int(*bool as i64)),
+        Value::Expr(node) => Some(node.clone()),
+        Value::Poly(p) => Some(p.node(pos)),
+        _ => None
+    }
+}
+pub fn expression(node: Node) -> Value {
+    match node {
+        Node::Int(int) => Value::Int(int),
+        Node::Float(float) => Value::Float(float),
+        node => Value::Expr(node)
+    }
+}
+pub fn symbolic_binary(op: &Token, left: &Value, right: &Value, pos: &Position) -> Option<Value> {
+    if [Token::Concat, Token::Hashtag].contains(op) { return None }
+    let node = binary_node(op.clone(), lift(left, pos)?, lift(right, pos)?, pos);
+    Some(expression(simplify_node(&node, pos)))
+}
+pub fn free_vars(node: &Node) -> Vec<String> {
+    fn walk(node: &Node, bound: &mut Vec<String>, vars: &mut Vec<String>) {
+        if let Node::Variable(name) = node {
+            if !bound.contains(name) && !vars.contains(name) { vars.push(name.clone()) }
+            return
+        }
+        let mut inside = |(node, _): &(Box<Node>, Position)| walk(node, bound, vars);
+        match node {
+            Node::Binary(_, left, right) | Node::Set(left, right) => { inside(left); inside(right) }
+            Node::Unary(_, node) | Node::Diff(node, _) => inside(node),
+            Node::Range(start, end, step) => { inside(start); inside(end); if let Some(step) = step { inside(step) } }
+            Node::Vector(nodes) | Node::Solve(nodes) | Node::Limit(_, nodes) => for (node, _) in nodes { walk(node, bound, vars) },
+            Node::Call((func, _), args) => {
+                if !matches!(func.as_ref(), Node::Variable(_)) { walk(func, bound, vars) }
+                for (node, _) in args { walk(node, bound, vars) }
+            }
+            Node::Series(_, var, (values, _), (body, _)) => {
+                walk(values, bound, vars);
+                bound.push(var.clone());
+                walk(body, bound, vars);
+                bound.pop();
+            }
+            _ => {}
+        }
+    }
+    let mut vars: Vec<String> = vec![];
+    walk(node, &mut vec![], &mut vars);
+    vars
+}
+pub fn substitute(value: Value, bindings: &[(Node, Position)], pos: &Position, file_path: &str, context: &mut Context)
+-> Result<Value, Error> {
+    let mut names: Vec<String> = vec![];
+    let mut nodes: Vec<(Node, Position)> = vec![];
+    for (binding, binding_pos) in bindings {
+        let Node::Binary(Token::Equal, (var, _), (node, node_pos)) = binding else {
+            return Err(Error::Syntax(format!("expected a binding like x=3 got {binding}"), binding_pos.clone(), file_path.to_string()))
+        };
+        let Node::Variable(name) = var.as_ref() else {
+            return Err(Error::Syntax(format!("expected a binding like x=3 got {binding}"), binding_pos.clone(), file_path.to_string()))
+        };
+        let with = interpret((node.as_ref(), node_pos), file_path, context)?;
+        let node = lift(&with, node_pos).ok_or_else(|| Error::IllegalValue(with.clone(), Type::Expr, node_pos.clone(), file_path.to_string()))?;
+        names.push(name.clone());
+        nodes.push((node, node_pos.clone()));
+    }
+    match value {
+        Value::Expr(node) => {
+            let mut scope = context.scope();
+            scope.symbolic = true;
+            let value = interpret((&inline(&names, &node, &nodes), pos), file_path, &mut scope)?;
+            context.rng = scope.rng;
+            Ok(value)
+        }
+        Value::Function(params, body) => {
+            let kept: Vec<String> = params.iter().filter(|param| !names.contains(param)).cloned().collect();
+            Ok(Value::Function(kept, simplify_node(&inline(&names, &body, &nodes), pos)))
+        }
+        Value::Vector(values) => Ok(Value::Vector(
+            values.into_iter().map(|value| substitute(value, bindings, pos, file_path, context)).collect::<Result<Vec<Value>, Error>>()?
+        )),
+        value => Ok(value)
+    }
+}
+
+// -- SIMPLIFICATION -------------------------------------------------------------------
+
+#[derive(Clone)]
+struct Term {
+    coeff: f64,
+    factors: Vec<(Node, Node)>
+}
+impl Term {
+    fn number(coeff: f64) -> Self { Self { coeff, factors: vec![] } }
+    fn factor(base: Node, exponent: Node) -> Self { Self { coeff: 1.0, factors: vec![(base, exponent)] } }
+    fn key(&self) -> String {
+        self.factors.iter().map(|(base, exponent)| format!("{base}^{exponent}")).collect::<Vec<String>>().join("*")
+    }
+    fn degree(&self) -> f64 {
+        self.factors.iter().filter(|(base, _)| matches!(base, Node::Variable(_))).filter_map(|(_, e)| constant(e)).sum()
+    }
+    fn push(&mut self, base: Node, exponent: Node, pos: &Position) {
+        if let (Some(b), Some(e)) = (constant(&base), constant(&exponent)) {
+            if e.fract() == 0.0 && (b != 0.0 || e > 0.0) { self.coeff *= b.powi(e as i32); return }
+        }
+        let key = format!("{base}");
+        match self.factors.iter_mut().find(|(b, _)| format!("{b}") == key) {
+            Some((_, e)) => *e = simplify_node(&add(e.clone(), exponent, pos), pos),
+            None => self.factors.push((base, exponent))
+        }
+        self.factors.retain(|(_, e)| !is(e, 0.0));
+    }
+    fn times(&self, other: &Term, pos: &Position) -> Term {
+        let mut term = self.clone();
+        term.coeff *= other.coeff;
+        for (base, exponent) in &other.factors { term.push(base.clone(), exponent.clone(), pos) }
+        term
+    }
+    fn normalize(mut self) -> Self {
+        let rank = |node: &Node| match node {
+            Node::Variable(_) => 0, Node::PI => 1, Node::Int(_) | Node::Float(_) => 2, Node::Call(_, _) => 3, _ => 4
+        };
+        self.factors.sort_by(|(a, _), (b, _)| rank(a).cmp(&rank(b)).then(format!("{a}").cmp(&format!("{b}"))));
+        self
+    }
+}
+pub fn fraction(number: f64) -> Option<(i64, i64)> {
+    if number.fract() == 0.0 { return (number.abs() < 1e15).then_some((number as i64, 1)) }
+    for den in 1..=1000 {
+        let num = (number * den as f64).round();
+        if num.abs() < 1e15 && (num / den as f64 - number).abs() <= 1e-12 * number.abs() { return Some((num as i64, den)) }
+    }
+    None
+}
+fn product_node(factors: Vec<Node>, pos: &Position) -> Node {
+    factors.into_iter().reduce(|acc, node| binary_node(Token::Multiply, acc, node, pos)).unwrap_or(Node::Int(1))
+}
+fn term_node(term: &Term, pos: &Position) -> Node {
+    let (mut num, mut den): (Vec<Node>, Vec<Node>) = (vec![], vec![]);
+    let (coeff_num, coeff_den) = match fraction(term.coeff.abs()) {
+        Some((n, d)) => (Node::Int(n), Node::Int(d)),
+        None => (Node::Float(term.coeff.abs()), Node::Int(1))
+    };
+    if !is(&coeff_num, 1.0) || term.factors.is_empty() { num.push(coeff_num) }
+    if !is(&coeff_den, 1.0) { den.push(coeff_den) }
+    let root = |base: &Node, e: f64| if e == 0.5 { apply("sqrt", base.clone(), pos) } else { pow(base.clone(), number_node(e), pos) };
+    for (base, exponent) in &term.factors {
+        match constant(exponent) {
+            Some(e) if e < 0.0 => den.push(root(base, -e)),
+            Some(e) => num.push(root(base, e)),
+            None => num.push(pow(base.clone(), exponent.clone(), pos))
+        }
+    }
+    if num.is_empty() { num.push(Node::Int(1)) }
+    if term.coeff < 0.0 { num[0] = neg(num[0].clone(), pos) }
+    let node = product_node(num, pos);
+    if den.is_empty() { return node }
+    binary_node(Token::Divide, node, product_node(den, pos), pos)
+}
+fn sum_node(terms: &[Term], pos: &Position) -> Node {
+    let mut terms = terms.to_vec();
+    if let Some(first) = terms.iter().position(|term| term.coeff > 0.0) { terms[..=first].rotate_right(1) }
+    let mut node: Option<Node> = None;
+    for term in &terms {
+        node = Some(match node {
+            None => term_node(term, pos),
+            Some(node) if term.coeff < 0.0 => {
+                let negated = Term { coeff: -term.coeff, factors: term.factors.clone() };
+                binary_node(Token::Subtract, node, term_node(&negated, pos), pos)
+            }
+            Some(node) => binary_node(Token::Add, node, term_node(term, pos), pos)
+        });
+    }
+    node.unwrap_or(Node::Int(0))
+}
+fn collect(terms: Vec<Term>) -> Vec<Term> {
+    let mut collected: Vec<Term> = vec![];
+    for term in terms.into_iter().map(Term::normalize) {
+        let key = term.key();
+        match collected.iter_mut().find(|t| t.key() == key) {
+            Some(t) => t.coeff += term.coeff,
+            None => collected.push(term)
+        }
+    }
+    collected.retain(|term| term.coeff != 0.0);
+    collected.sort_by(|a, b| b.degree().total_cmp(&a.degree())
+        .then(a.factors.is_empty().cmp(&b.factors.is_empty())).then(a.key().cmp(&b.key())));
+    collected
+}
+fn opaque(terms: Vec<Term>, pos: &Position) -> Node {
+    sum_node(&collect(terms), pos)
+}
+fn product(left: Vec<Term>, right: Vec<Term>, pos: &Position) -> Vec<Term> {
+    if left.len() > 1 && right.len() > 1 {
+        let (left, right) = (opaque(left, pos), opaque(right, pos));
+        return vec![Term::factor(left, Node::Int(1)).times(&Term::factor(right, Node::Int(1)), pos)]
+    }
+    left.iter().flat_map(|l| right.iter().map(|r| l.times(r, pos))).collect()
+}
+fn reciprocal(terms: Vec<Term>, pos: &Position) -> Vec<Term> {
+    match terms.as_slice() {
+        [term] if term.coeff != 0.0 => {
+            let mut inverse = Term::number(1.0 / term.coeff);
+            for (base, exponent) in &term.factors { inverse.push(base.clone(), neg(exponent.clone(), pos), pos) }
+            vec![inverse]
+        }
+        _ => vec![Term::factor(opaque(terms, pos), Node::Int(-1))]
+    }
+}
+fn power(terms: Vec<Term>, exponent: Node, pos: &Position) -> Vec<Term> {
+    let n = constant(&exponent);
+    if n == Some(0.0) { return vec![Term::number(1.0)] }
+    if n == Some(1.0) { return terms }
+    match (terms.as_slice(), n) {
+        ([term], Some(n)) if n.fract() == 0.0 && term.factors.iter().all(|(_, e)| constant(e).is_some_and(|e| e.fract() == 0.0)) => {
+            let mut result = Term::number(term.coeff.powi(n as i32));
+            for (base, e) in &term.factors { result.push(base.clone(), simplify_node(&mul(e.clone(), exponent.clone(), pos), pos), pos) }
+            vec![result]
+        }
+        ([term], _) if term.coeff == 1.0 && term.factors.len() == 1 && is(&term.factors[0].1, 1.0) =>
+            vec![Term::factor(term.factors[0].0.clone(), exponent)],
+        ([term], Some(n)) if term.factors.is_empty() && term.coeff.powf(n).fract() == 0.0 => vec![Term::number(term.coeff.powf(n))],
+        ([term], Some(n)) if n == 0.5 && term.factors.is_empty() && term.coeff > 0.0 && term.coeff.fract() == 0.0 && term.coeff < 1e12 => {
+            let radicand = term.coeff as i64;
+            let root = (2..=(radicand as f64).sqrt() as i64).rev().find(|k| radicand % (k * k) == 0).unwrap_or(1);
+            vec![Term { coeff: root as f64, factors: vec![(Node::Int(radicand / (root * root)), exponent)] }]
+        }
+        ([term], _) if term.factors.is_empty() => vec![Term::factor(number_node(term.coeff), exponent)],
+        _ => vec![Term::factor(opaque(terms, pos), exponent)]
+    }
+}
+fn fold_call(name: &str, arg: &Node) -> Option<Node> {
+    let x = constant(arg)?;
+    let y = match name {
+        "sin" => x.sin(), "cos" => x.cos(), "tan" => x.tan(), "exp" => x.exp(), "ln" => x.ln(),
+        "sqrt" => x.sqrt(), "abs" => x.abs(), "sinh" => x.sinh(), "cosh" => x.cosh(), "tanh" => x.tanh(),
+        "asin" => x.asin(), "atan" => x.atan(), "log" => x.log10(),
+        _ => return None
+    };
+    (y.fract() == 0.0 && y.is_finite()).then(|| number_node(y))
+}
+fn simplify_children(node: &Node, pos: &Position) -> Node {
+    let simp = |(node, node_pos): &(Box<Node>, Position)| (Box::new(simplify_node(node, pos)), node_pos.clone());
+    let simp_all = |nodes: &Vec<(Node, Position)>| nodes.iter().map(|(node, node_pos)| (simplify_node(node, pos), node_pos.clone())).collect();
+    match node {
+        Node::Binary(op, left, right) => Node::Binary(op.clone(), simp(left), simp(right)),
+        Node::Unary(op, node) => Node::Unary(op.clone(), simp(node)),
+        Node::Vector(nodes) => Node::Vector(simp_all(nodes)),
+        Node::Set(target, expr) => Node::Set(target.clone(), simp(expr)),
+        Node::Range(start, end, step) => Node::Range(simp(start), simp(end), step.as_ref().map(simp)),
+        Node::Series(op, bound, values, body) => Node::Series(op.clone(), bound.clone(), simp(values), simp(body)),
+        Node::Diff(func, bound) => Node::Diff(simp(func), bound.clone()),
+        Node::Call(func, args) => {
+            let args: Vec<(Node, Position)> = simp_all(args);
+            if let (Node::Variable(name), [(arg, _)]) = (func.0.as_ref(), args.as_slice()) {
+                if let Some(folded) = fold_call(name, arg) { return folded }
+            }
+            Node::Call(func.clone(), args)
+        }
+        _ => node.clone()
+    }
+}
+fn expand(node: &Node, pos: &Position) -> Vec<Term> {
+    match node {
+        Node::Int(_) | Node::Float(_) => {
+            let number = constant(node).unwrap_or(0.0);
+            if number == 0.0 { vec![] } else { vec![Term::number(number)] }
+        }
+        Node::Binary(Token::Add, (l, _), (r, _)) => [expand(l, pos), expand(r, pos)].concat(),
+        Node::Binary(Token::Subtract, (l, _), (r, _)) =>
+            [expand(l, pos), product(vec![Term::number(-1.0)], expand(r, pos), pos)].concat(),
+        Node::Unary(Token::Subtract, (u, _)) => product(vec![Term::number(-1.0)], expand(u, pos), pos),
+        Node::Binary(Token::Multiply, (l, _), (r, _)) => product(expand(l, pos), expand(r, pos), pos),
+        Node::Binary(Token::Divide, (l, _), (r, _)) => {
+            let (num, den) = (collect(expand(l, pos)), collect(expand(r, pos)));
+            match den.as_slice() {
+                [term] if num.is_empty() && term.factors.is_empty() => vec![],
+                _ if num.is_empty() || den.is_empty() => vec![Term::factor(simplify_children(node, pos), Node::Int(1))],
+                _ => product(num, reciprocal(den, pos), pos)
+            }
+        }
+        Node::Binary(Token::Power, (l, _), (r, _)) => power(expand(l, pos), simplify_node(r, pos), pos),
+        Node::Call((func, _), args) if matches!(func.as_ref(), Node::Variable(name) if name == "sqrt") && args.len() == 1 =>
+            power(expand(&args[0].0, pos), Node::Float(0.5), pos),
+        _ => match simplify_children(node, pos) {
+            simplified if constant(&simplified).is_some() => expand(&simplified, pos),
+            simplified => vec![Term::factor(simplified, Node::Int(1))]
+        }
+    }
+}
+pub fn simplify_node(node: &Node, pos: &Position) -> Node {
+    let arithmetic = |node: &Node| matches!(node, Node::Binary(
+        Token::Add | Token::Subtract | Token::Multiply | Token::Divide | Token::Power, _, _
+    ) | Node::Unary(Token::Subtract, _));
+    if !arithmetic(node) || any_node(node, &|node| matches!(node, Node::Infinity)) { return simplify_children(node, pos) }
+    sum_node(&collect(expand(node, pos)), pos)
+}
+
+// -- SOLVING --------------------------------------------------------------------------
+
+pub fn polynomial_coeffs(node: &Node, var: &str, pos: &Position) -> Option<Vec<Node>> {
+    fn poly(node: &Node, var: &str, pos: &Position) -> Option<Vec<Node>> {
+        if !contains(node, var) { return Some(vec![node.clone()]) }
+        let combine = |l: Vec<Node>, r: Vec<Node>, op: fn(Node, Node, &Position) -> Node| -> Vec<Node> {
+            (0..l.len().max(r.len())).map(|i| op(
+                l.get(i).cloned().unwrap_or(Node::Int(0)), r.get(i).cloned().unwrap_or(Node::Int(0)), pos
+            )).collect()
+        };
+        let times = |l: &[Node], r: &[Node]| -> Vec<Node> {
+            let mut result = vec![Node::Int(0); l.len() + r.len() - 1];
+            for (i, a) in l.iter().enumerate() {
+                for (j, b) in r.iter().enumerate() { result[i + j] = add(result[i + j].clone(), mul(a.clone(), b.clone(), pos), pos) }
+            }
+            result
+        };
+        match node {
+            Node::Variable(_) => Some(vec![Node::Int(0), Node::Int(1)]),
+            Node::Unary(Token::Subtract, (u, _)) => Some(poly(u, var, pos)?.into_iter().map(|c| neg(c, pos)).collect()),
+            Node::Binary(Token::Add, (l, _), (r, _)) => Some(combine(poly(l, var, pos)?, poly(r, var, pos)?, add)),
+            Node::Binary(Token::Subtract, (l, _), (r, _)) => Some(combine(poly(l, var, pos)?, poly(r, var, pos)?, sub)),
+            Node::Binary(Token::Multiply, (l, _), (r, _)) => Some(times(&poly(l, var, pos)?, &poly(r, var, pos)?)),
+            Node::Binary(Token::Divide, (l, _), (r, _)) if !contains(r, var) =>
+                Some(poly(l, var, pos)?.into_iter().map(|c| div(c, r.as_ref().clone(), pos)).collect()),
+            Node::Binary(Token::Power, (l, _), (r, _)) => {
+                let n = constant(r)?;
+                if n < 0.0 || n.fract() != 0.0 || n > 64.0 { return None }
+                let base = poly(l, var, pos)?;
+                Some((0..n as usize).fold(vec![Node::Int(1)], |acc, _| times(&acc, &base)))
+            }
+            _ => None
+        }
+    }
+    let mut coeffs: Vec<Node> = poly(node, var, pos)?.iter().map(|c| simplify_node(c, pos)).collect();
+    while coeffs.len() > 1 && coeffs.last().is_some_and(|c| is(c, 0.0)) { coeffs.pop(); }
+    Some(coeffs)
+}
+pub fn solve_polynomial(node: &Node, var: &str, pos: &Position) -> Option<Value> {
+    let coeffs = polynomial_coeffs(node, var, pos)?;
+    let simp = |node: Node| simplify_node(&node, pos);
+    let roots = |nodes: Vec<Node>| Some(Value::Vector(nodes.into_iter().map(expression).collect()));
+    match coeffs.as_slice() {
+        [c, b] => Some(expression(simp(div(neg(c.clone(), pos), b.clone(), pos)))),
+        [c, b, a] => {
+            let disc = simp(sub(pow(b.clone(), Node::Int(2), pos), mul(Node::Int(4), mul(a.clone(), c.clone(), pos), pos), pos));
+            let root = |sign: f64| simp(div(
+                add(neg(b.clone(), pos), mul(number_node(sign), apply("sqrt", disc.clone(), pos), pos), pos),
+                mul(Node::Int(2), a.clone(), pos), pos
+            ));
+            match (constant(&disc), constant(a)) {
+                (Some(d), Some(a)) if d < 0.0 => {
+                    // + 0.0 keeps a zero real part from showing as -0
+                    let (re, im) = (-constant(b)? / (2.0 * a) + 0.0, (-d).sqrt() / (2.0 * a).abs());
+                    Some(Value::Vector(vec![Value::Complex(Complex::new(re, -im)), Value::Complex(Complex::new(re, im))]))
+                }
+                (Some(d), _) if d < 0.0 => None,
+                (Some(0.0), _) => roots(vec![simp(div(neg(b.clone(), pos), mul(Node::Int(2), a.clone(), pos), pos))]),
+                (Some(_), Some(a)) if a < 0.0 => roots(vec![root(1.0), root(-1.0)]),
+                _ => roots(vec![root(-1.0), root(1.0)])
+            }
+        }
+        _ => None
+    }
+}
+pub fn solve_linear_system(nodes: &[Node], vars: &[String], pos: &Position, file_path: &str) -> Result<Vec<Node>, Error> {
+    let nonlinear = || Error::Domain("solve only handles linear systems of equations".to_string(), pos.clone(), file_path.to_string());
+    if nodes.len() != vars.len() { return Err(Error::Shape(vars.len(), nodes.len(), pos.clone(), file_path.to_string())) }
+    let mut rows: Vec<Vec<Node>> = vec![];
+    for node in nodes {
+        let mut row: Vec<Node> = vec![];
+        for var in vars {
+            let coeffs = polynomial_coeffs(node, var, pos).ok_or_else(nonlinear)?;
+            match coeffs.as_slice() {
+                [_] => row.push(Node::Int(0)),
+                [_, coeff] if vars.iter().all(|var| !contains(coeff, var)) => row.push(coeff.clone()),
+                _ => return Err(nonlinear())
+            }
+        }
+        let rest = vars.iter().fold(node.clone(), |node, var| node.substitute(var, &Node::Int(0)));
+        row.push(simplify_node(&neg(rest, pos), pos));
+        rows.push(row);
+    }
+    let n = vars.len();
+    for col in 0..n {
+        let pivot = (col..n).find(|&r| !is(&rows[r][col], 0.0)).ok_or_else(|| Error::Domain(
+            "solve found no unique solution for the system".to_string(), pos.clone(), file_path.to_string()
+        ))?;
+        rows.swap(col, pivot);
+        for r in 0..n {
+            if r == col || is(&rows[r][col], 0.0) { continue }
+            let factor = simplify_node(&div(rows[r][col].clone(), rows[col][col].clone(), pos), pos);
+            let pivot_row = rows[col].clone();
+            for (entry, pivot) in rows[r].iter_mut().zip(pivot_row).skip(col) {
+                *entry = simplify_node(&sub(entry.clone(), mul(factor.clone(), pivot, pos), pos), pos);
+            }
+        }
+    }
+    Ok(rows.iter().enumerate().map(|(i, row)| simplify_node(&div(row[n].clone(), row[i].clone(), pos), pos)).collect())
+}
+
+// -- DIFFERENTIATION ------------------------------------------------------------------
+
+struct Differ<'a> {
+    var: &'a str,
+    pos: &'a Position,
+    file_path: &'a str,
+    inlining: Vec<String>
+}
+impl Differ<'_> {
+    fn fail(&self, node: &Node) -> Error {
+        Error::Domain(format!("cannot differentiate {node}"), self.pos.clone(), self.file_path.to_string())
+    }
+    fn outer(&self, name: &str, u: &Node) -> Option<Node> {
+        let pos = self.pos;
+        let u = || u.clone();
+        let one = || Node::Int(1);
+        Some(match name {
+            "sin" => apply("cos", u(), pos),
+            "cos" => neg(apply("sin", u(), pos), pos),
+            "tan" => div(one(), pow(apply("cos", u(), pos), Node::Int(2), pos), pos),
+            "asin" => div(one(), apply("sqrt", sub(one(), pow(u(), Node::Int(2), pos), pos), pos), pos),
+            "acos" => neg(div(one(), apply("sqrt", sub(one(), pow(u(), Node::Int(2), pos), pos), pos), pos), pos),
+            "atan" => div(one(), add(one(), pow(u(), Node::Int(2), pos), pos), pos),
+            "sinh" => apply("cosh", u(), pos),
+            "cosh" => apply("sinh", u(), pos),
+            "tanh" => div(one(), pow(apply("cosh", u(), pos), Node::Int(2), pos), pos),
+            "exp" => apply("exp", u(), pos),
+            "ln" => div(one(), u(), pos),
+            "log" => div(one(), mul(u(), apply("ln", Node::Int(10), pos), pos), pos),
+            "sqrt" => div(one(), mul(Node::Int(2), apply("sqrt", u(), pos), pos), pos),
+            "abs" => div(u(), apply("abs", u(), pos), pos),
+            _ => return None
+        })
+    }
+    fn derive(&mut self, node: &Node, context: &mut Context) -> Result<Node, Error> {
+        let pos = self.pos;
+        if !contains(node, self.var) { return Ok(Node::Int(0)) }
+        match node {
+            Node::Variable(_) => Ok(Node::Int(1)),
+            Node::Unary(Token::Subtract, (u, _)) => Ok(neg(self.derive(u, context)?, pos)),
+            Node::Binary(op, (l, _), (r, _)) => {
+                let (dl, dr) = (self.derive(l, context)?, self.derive(r, context)?);
+                let (l, r) = (l.as_ref().clone(), r.as_ref().clone());
+                match op {
+                    Token::Add => Ok(add(dl, dr, pos)),
+                    Token::Subtract => Ok(sub(dl, dr, pos)),
+                    Token::Multiply => Ok(add(mul(dl, r.clone(), pos), mul(l, dr, pos), pos)),
+                    Token::Divide => Ok(div(
+                        sub(mul(dl, r.clone(), pos), mul(l, dr, pos), pos), pow(r, Node::Int(2), pos), pos
+                    )),
+                    Token::Power if is(&dr, 0.0) => {
+                        let exponent = sub(r.clone(), Node::Int(1), pos);
+                        Ok(mul(mul(r, pow(l, exponent, pos), pos), dl, pos))
+                    }
+                    Token::Power if is(&dl, 0.0) => Ok(mul(mul(node.clone(), apply("ln", l, pos), pos), dr, pos)),
+                    Token::Power => Ok(mul(node.clone(), add(
+                        mul(dr, apply("ln", l.clone(), pos), pos), div(mul(r, dl, pos), l, pos), pos
+                    ), pos)),
+                    _ => Err(self.fail(node))
+                }
+            }
+            Node::Call((func, _), args) => {
+                let Node::Variable(name) = func.as_ref() else { return Err(self.fail(node)) };
+                match context.get(name) {
+                    Some(Value::Function(params, body)) if params.len() == args.len() => {
+                        if self.inlining.contains(name) { return Err(Error::Domain(
+                            format!("cannot differentiate recursive function {name}"), pos.clone(), self.file_path.to_string()
+                        )) }
+                        self.inlining.push(name.clone());
+                        let result = self.derive(&inline(&params, &body, args), context);
+                        self.inlining.pop();
+                        result
+                    }
+                    None if args.len() == 1 => {
+                        let du = self.derive(&args[0].0, context)?;
+                        let outer = self.outer(name, &args[0].0).ok_or_else(|| self.fail(node))?;
+                        Ok(mul(outer, du, pos))
+                    }
+                    _ => Err(self.fail(node))
+                }
+            }
+            Node::Vector(nodes) => {
+                let mut vector: Vec<(Node, Position)> = vec![];
+                for (node, node_pos) in nodes { vector.push((self.derive(node, context)?, node_pos.clone())) }
+                Ok(Node::Vector(vector))
+            }
+            Node::Series(Token::Sum, bound, (values, values_pos), (body, body_pos)) if !contains(values, self.var) =>
+                Ok(Node::Series(Token::Sum, bound.clone(), (values.clone(), values_pos.clone()),
+                    (Box::new(self.derive(body, context)?), body_pos.clone()))),
+            _ => Err(self.fail(node))
+        }
+    }
+}
+// function bodies run in a scope holding only their parameters, so other names must be builtins or symbolic values
+fn function_scope(params: &[String], body: &Node, pos: &Position, file_path: &str, context: &Context) -> Result<Context, Error> {
+    let undefined = |name: &String| !params.contains(name) && builtin(name).is_none();
+    let missing = RefCell::new(free_vars(body).into_iter().find(|var| !context.symbolic && undefined(var)));
+    any_node(body, &|node| missing.borrow().is_some() || match node {
+        Node::Call((func, _), _) => matches!(func.as_ref(), Node::Variable(name) if undefined(name) && missing.replace(Some(name.clone())).is_none()),
+        _ => false
+    });
+    match missing.into_inner() {
+        Some(var) => Err(Error::Variable(var, pos.clone(), file_path.to_string())),
+        None => Ok(context.scope())
+    }
+}
+pub fn derivative(node: &Node, var: &str, pos: &Position, file_path: &str, context: &mut Context) -> Result<Node, Error> {
+    Differ { var, pos, file_path, inlining: vec![] }.derive(node, context)
+}
+pub fn differentiate(name: &str, func: &(Value, Position), var: Option<&str>, file_path: &str, context: &mut Context)
+-> Result<Value, Error> {
+    let (params, body) = match &func.0 {
+        Value::Expr(node) => {
+            let vars = free_vars(node);
+            let var = match (var, vars.as_slice()) {
+                (Some(var), _) => var.to_string(),
+                (None, [var]) => var.clone(),
+                _ => return Err(Error::Domain(
+                    format!("{name} expects an expression in one variable, got {}", vars.join(" ")), func.1.clone(), file_path.to_string()
+                ))
+            };
+            let node = derivative(node, &var, &func.1, file_path, context)?;
+            return Ok(expression(simplify_node(&node, &func.1)))
+        }
+        Value::Poly(p) => return Ok(Value::Poly(p.derivative())),
+        Value::Function(params, body) => (params.clone(), body.clone()),
+        Value::Builtin(builtin) => (vec!["x".to_string()], apply(builtin, Node::Variable("x".to_string()), &func.1)),
+        _ => return Err(illegal_arg(name, func, file_path))
+    };
+    let Some(var) = var.or(params.first().map(|param| param.as_str())) else {
+        return Err(Error::Domain(format!("{name} expects a function with parameters"), func.1.clone(), file_path.to_string()))
+    };
+    let mut scope = function_scope(&params, &body, &func.1, file_path, context)?;
+    let body = derivative(&body, var, &func.1, file_path, &mut scope)?;
+    Ok(Value::Function(params, simplify_node(&body, &func.1)))
+}
+
+// -- INTEGRATION ----------------------------------------------------------------------
+
+struct Integrator<'a> {
+    var: &'a str,
+    pos: &'a Position,
+    file_path: &'a str,
+    inlining: Vec<String>
+}
+impl Integrator<'_> {
+    fn x(&self) -> Node { Node::Variable(self.var.to_string()) }
+    fn linear(&self, node: &Node) -> Option<Node> {
+        match polynomial_coeffs(node, self.var, self.pos)?.as_slice() {
+            [_, slope] => Some(slope.clone()),
+            _ => None
+        }
+    }
+    fn outer(&self, name: &str, u: &Node) -> Option<Node> {
+        let pos = self.pos;
+        let u = || u.clone();
+        Some(match name {
+            "exp" => apply("exp", u(), pos),
+            "sin" => neg(apply("cos", u(), pos), pos),
+            "cos" => apply("sin", u(), pos),
+            "tan" => neg(apply("ln", apply("abs", apply("cos", u(), pos), pos), pos), pos),
+            "sinh" => apply("cosh", u(), pos),
+            "cosh" => apply("sinh", u(), pos),
+            "tanh" => apply("ln", apply("cosh", u(), pos), pos),
+            "ln" => sub(mul(u(), apply("ln", u(), pos), pos), u(), pos),
+            _ => return None
+        })
+    }
+    fn factors(&self, node: &Node) -> Vec<Node> {
+        match node {
+            Node::Binary(Token::Multiply, (l, _), (r, _)) => [self.factors(l), self.factors(r)].concat(),
+            Node::Binary(Token::Divide, (l, _), (r, _)) => {
+                let invert = |f: Node| match &f {
+                    Node::Binary(Token::Power, (base, _), (exponent, _)) if constant(exponent).is_some() =>
+                        pow(base.as_ref().clone(), neg(exponent.as_ref().clone(), self.pos), self.pos),
+                    _ => pow(f, Node::Int(-1), self.pos)
+                };
+                [self.factors(l), self.factors(r).into_iter().map(invert).collect()].concat()
+            }
+            Node::Unary(Token::Subtract, (u, _)) => [vec![Node::Int(-1)], self.factors(u)].concat(),
+            Node::Call((func, _), args) if matches!(func.as_ref(), Node::Variable(name) if name == "sqrt") && args.len() == 1 =>
+                vec![pow(args[0].0.clone(), Node::Float(0.5), self.pos)],
+            node => vec![node.clone()]
+        }
+    }
+    fn rational(&self, base: &Node) -> Option<Node> {
+        let pos = self.pos;
+        let coeffs = polynomial_coeffs(base, self.var, pos)?;
+        let [c, b, a] = coeffs.iter().map(constant).collect::<Option<Vec<f64>>>()?.try_into().ok()?;
+        let disc = b * b - 4.0 * a * c;
+        let shifted = add(mul(number_node(2.0 * a), self.x(), pos), number_node(b), pos);
+        if disc < 0.0 {
+            let root = apply("sqrt", number_node(-disc), pos);
+            return Some(mul(div(Node::Int(2), root.clone(), pos), apply("atan", div(shifted, root, pos), pos), pos))
+        }
+        if disc == 0.0 { return Some(div(Node::Int(-2), shifted, pos)) }
+        let root = apply("sqrt", number_node(disc), pos);
+        let log = |sign: f64| apply("ln", apply("abs", add(shifted.clone(), mul(number_node(sign), root.clone(), pos), pos), pos), pos);
+        Some(div(sub(log(-1.0), log(1.0), pos), root, pos))
+    }
+    fn single(&mut self, node: &Node, context: &mut Context) -> Option<Node> {
+        let pos = self.pos;
+        match node {
+            Node::Variable(_) => Some(div(pow(self.x(), Node::Int(2), pos), Node::Int(2), pos)),
+            Node::Binary(Token::Power, (base, _), (exponent, _)) if !contains(exponent, self.var) => {
+                let Some(slope) = self.linear(base) else {
+                    return if is(exponent, -1.0) { self.rational(base) } else { None }
+                };
+                if is(exponent, -1.0) { return Some(div(apply("ln", apply("abs", base.as_ref().clone(), pos), pos), slope, pos)) }
+                let raised = add(exponent.as_ref().clone(), Node::Int(1), pos);
+                Some(div(pow(base.as_ref().clone(), raised.clone(), pos), mul(raised, slope, pos), pos))
+            }
+            Node::Binary(Token::Power, (base, _), (exponent, _)) if !contains(base, self.var) => {
+                let slope = self.linear(exponent)?;
+                Some(div(node.clone(), mul(apply("ln", base.as_ref().clone(), pos), slope, pos), pos))
+            }
+            Node::Call((func, _), args) => {
+                let Node::Variable(name) = func.as_ref() else { return None };
+                match context.get(name) {
+                    Some(Value::Function(params, body)) if params.len() == args.len() && !self.inlining.contains(name) => {
+                        self.inlining.push(name.clone());
+                        let result = self.integrate(&simplify_node(&inline(&params, &body, args), pos), context);
+                        self.inlining.pop();
+                        result
+                    }
+                    None if args.len() == 1 => {
+                        if name == "sqrt" { return self.single(&pow(args[0].0.clone(), Node::Float(0.5), pos), context) }
+                        let slope = self.linear(&args[0].0)?;
+                        Some(div(self.outer(name, &args[0].0)?, slope, pos))
+                    }
+                    _ => None
+                }
+            }
+            _ => None
+        }
+    }
+    fn substitution(&mut self, outer: &Node, rest: &Node, context: &mut Context) -> Option<Node> {
+        let pos = self.pos;
+        let (inner, replaced) = match outer {
+            Node::Binary(Token::Power, (base, _), (exponent, _)) if !contains(exponent, self.var) =>
+                (base.as_ref().clone(), pow(self.x(), exponent.as_ref().clone(), pos)),
+            Node::Binary(Token::Power, (base, _), (exponent, _)) if !contains(base, self.var) =>
+                (exponent.as_ref().clone(), pow(base.as_ref().clone(), self.x(), pos)),
+            Node::Call((func, _), args) if args.len() == 1 && matches!(func.as_ref(), Node::Variable(name) if context.get(name).is_none()) =>
+                (args[0].0.clone(), Node::Call(((*func).clone(), pos.clone()), vec![(self.x(), pos.clone())])),
+            _ => (outer.clone(), self.x())
+        };
+        if let Some(result) = self.substitute_inner(&inner, &replaced, rest, context) { return Some(result) }
+        self.substitute_inner(outer, &self.x(), rest, context)
+    }
+    fn substitute_inner(&mut self, inner: &Node, replaced: &Node, rest: &Node, context: &mut Context) -> Option<Node> {
+        let pos = self.pos;
+        let slope = derivative(inner, self.var, pos, self.file_path, context).ok()?;
+        let ratio = simplify_node(&div(rest.clone(), slope, pos), pos);
+        if contains(&ratio, self.var) { return None }
+        let antiderivative = self.single(replaced, context)?;
+        Some(mul(ratio, antiderivative.substitute(self.var, inner), pos))
+    }
+    fn parts(&mut self, poly: &Node, other: &Node, context: &mut Context) -> Option<Node> {
+        let pos = self.pos;
+        let parts_friendly = match other {
+            Node::Call((func, _), _) => matches!(func.as_ref(), Node::Variable(name) if ["exp", "sin", "cos", "sinh", "cosh"].contains(&name.as_str())),
+            Node::Binary(Token::Power, (base, _), _) => !contains(base, self.var),
+            _ => false
+        };
+        if !parts_friendly { return None }
+        polynomial_coeffs(poly, self.var, pos)?;
+        let antiderivative = self.single(other, context)?;
+        let slope = derivative(poly, self.var, pos, self.file_path, context).ok()?;
+        let rest = self.integrate(&simplify_node(&mul(slope, antiderivative.clone(), pos), pos), context)?;
+        Some(sub(mul(poly.clone(), antiderivative, pos), rest, pos))
+    }
+    fn product(&mut self, node: &Node, context: &mut Context) -> Option<Node> {
+        let pos = self.pos;
+        let (consts, vars): (Vec<Node>, Vec<Node>) = self.factors(node).into_iter().partition(|f| !contains(f, self.var));
+        let scale = consts.into_iter().fold(Node::Int(1), |acc, f| mul(acc, f, pos));
+        let result = match vars.as_slice() {
+            [] => Some(self.x()),
+            [single] => self.single(single, context),
+            _ => (0..vars.len()).find_map(|i| {
+                let rest = vars.iter().enumerate().filter(|(j, _)| *j != i).fold(Node::Int(1), |acc, (_, f)| mul(acc, f.clone(), pos));
+                self.substitution(&vars[i], &rest, context).or_else(|| self.parts(&rest, &vars[i], context))
+            })
+        };
+        Some(mul(scale, result?, pos))
+    }
+    fn integrate(&mut self, node: &Node, context: &mut Context) -> Option<Node> {
+        let pos = self.pos;
+        if !contains(node, self.var) { return Some(mul(node.clone(), self.x(), pos)) }
+        if let Some(coeffs) = polynomial_coeffs(node, self.var, pos) {
+            return Some(coeffs.into_iter().enumerate().fold(Node::Int(0), |acc, (i, c)| {
+                let power = Node::Int(i as i64 + 1);
+                add(acc, div(mul(c, pow(self.x(), power.clone(), pos), pos), power, pos), pos)
+            }))
+        }
+        match node {
+            Node::Binary(Token::Add, (l, _), (r, _)) => Some(add(self.integrate(l, context)?, self.integrate(r, context)?, pos)),
+            Node::Binary(Token::Subtract, (l, _), (r, _)) => Some(sub(self.integrate(l, context)?, self.integrate(r, context)?, pos)),
+            Node::Binary(Token::Multiply | Token::Divide, _, _) | Node::Unary(Token::Subtract, _) => self.product(node, context),
+            node => self.single(node, context)
+        }
+    }
+}
+pub fn antiderivative(node: &Node, var: &str, pos: &Position, file_path: &str, context: &mut Context) -> Result<Node, Error> {
+    let node = simplify_node(node, pos);
+    let result = Integrator { var, pos, file_path, inlining: vec![] }.integrate(&node, context).ok_or_else(|| Error::Domain(
+        format!("no closed form found for the integral of {node}"), pos.clone(), file_path.to_string()
+    ))?;
+    Ok(simplify_node(&result, pos))
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+pub fn diff(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("diff", args, 2, pos, file_path)?;
+    let var = match (&args[0].0, &args[1].0) {
+        (Value::Function(params, _), Value::Int(i)) => usize::try_from(*i).ok().and_then(|i| params.get(i)).cloned()
+            .ok_or_else(|| Error::Index(*i, params.len(), args[1].1.clone(), file_path.to_string()))?,
+        (_, Value::Expr(Node::Variable(var))) => var.clone(),
+        _ => return Err(illegal_arg("diff", &args[1], file_path))
+    };
+    differentiate("diff", &args[0], Some(&var), file_path, context)
+}
+pub fn d(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("d", args, 1, pos, file_path)?;
+    differentiate("d", &args[0], None, file_path, context)
+}
+pub fn simplify(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("simplify", args, 1, pos, file_path)?;
+    match &args[0].0 {
+        Value::Function(params, body) => Ok(Value::Function(params.clone(), simplify_node(body, &args[0].1))),
+        Value::Expr(node) => Ok(expression(simplify_node(node, &args[0].1))),
+        _ => Err(illegal_arg("simplify", &args[0], file_path))
+    }
+}
+pub fn symbolic(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("symbolic", args, 1, pos, file_path)?;
+    let on = args[0].0.number().ok_or_else(|| illegal_arg("symbolic", &args[0], file_path))?;
+    context.symbolic = on != 0.0;
+    Ok(Value::Bool(context.symbolic))
+}
+pub fn int(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("int", args, 1, pos, file_path)?;
+    let (func, func_pos) = &args[0];
+    match func {
+        Value::Poly(p) => Ok(Value::Poly(p.integral())),
+        Value::Expr(node) => match free_vars(node).as_slice() {
+            [var] => Ok(expression(antiderivative(node, var, func_pos, file_path, context)?)),
+            vars => Err(Error::Domain(
+                format!("int expects an expression in one variable, got {}", vars.join(" ")), func_pos.clone(), file_path.to_string()
+            ))
+        }
+        Value::Function(params, body) => {
+            let Some(var) = params.first() else {
+                return Err(Error::Domain("int expects a function with parameters".to_string(), func_pos.clone(), file_path.to_string()))
+            };
+            let mut scope = function_scope(params, body, func_pos, file_path, context)?;
+            Ok(Value::Function(params.clone(), antiderivative(body, var, func_pos, file_path, &mut scope)?))
+        }
+        Value::Builtin(builtin) => {
+            let body = antiderivative(&apply(builtin, Node::Variable("x".to_string()), func_pos), "x", func_pos, file_path, context)?;
+            Ok(Value::Function(vec!["x".to_string()], body))
+        }
+        _ => Err(illegal_arg("int", &args[0], file_path))
+    }
+}
+pub fn taylor(args: &[(Value, Position)], pos: &Position, file_path: &str, context: &mut Context) -> Result<Value, Error> {
+    expect_args("taylor", args, 3, pos, file_path)?;
+    let (func, func_pos) = &args[0];
+    let (params, body) = match func {
+        Value::Function(params, body) if !params.is_empty() => (params.clone(), body.clone()),
+        Value::Builtin(builtin) => (vec!["x".to_string()], apply(builtin, Node::Variable("x".to_string()), func_pos)),
+        Value::Poly(p) => (vec![p.var.clone()], p.node(func_pos)),
+        Value::Expr(node) => match free_vars(node).as_slice() {
+            [var] => (vec![var.clone()], node.clone()),
+            vars => return Err(Error::Domain(
+                format!("taylor expects an expression in one variable, got {}", vars.join(" ")), func_pos.clone(), file_path.to_string()
+            ))
+        }
+        _ => return Err(illegal_arg("taylor", &args[0], file_path))
+    };
+    let (a, n) = (expect_float("taylor", &args[1], file_path)?, expect_int("taylor", &args[2], file_path)?);
+    if n < 0 { return Err(illegal_arg("taylor", &args[2], file_path)) }
+    let mut scope = match func {
+        Value::Function(_, _) => function_scope(&params, &body, func_pos, file_path, context)?,
+        _ => context.clone()
+    };
+    let context = &mut scope;
+    let (var, center) = (&params[0], number_node(a));
+    let (mut derived, mut factorial) = (body, 1.0);
+    let mut series = Node::Int(0);
+    for k in 0..=n {
+        if k > 0 {
+            derived = simplify_node(&derivative(&derived, var, func_pos, file_path, context)?, func_pos);
+            factorial *= k as f64;
+        }
+        let mut coeff = simplify_node(&derived.substitute(var, &center), func_pos);
+        let value_at = |x: f64| -> Result<Option<f64>, Error> {
+            let node = derived.substitute(var, &number_node(x));
+            if !free_vars(&node).is_empty() { return Ok(None) }
+            match interpret((&node, func_pos), file_path, &mut context.scope()) {
+                Ok(value) => Ok(Some(value.number().unwrap_or(f64::NAN))),
+                Err(Error::Domain(_, _, _)) => Ok(Some(f64::NAN)),
+                Err(error) => Err(error)
+            }
+        };
+        if let Some(number) = value_at(a)? {
+            // the derivative has to be finite at a and agree with its values just beside a
+            let h = 1e-8 * (1.0 + a.abs());
+            let (left, right) = (value_at(a - h)?.unwrap_or(f64::NAN), value_at(a + h)?.unwrap_or(f64::NAN));
+            let jump = (left - number).abs().max((right - number).abs());
+            if !number.is_finite() || jump.is_nan() || jump > 1e-4 * (1.0 + number.abs()) {
+                return Err(Error::Domain(format!("taylor needs derivative {k} to be finite and continuous at {a}"), func_pos.clone(), file_path.to_string()))
+            }
+            if matches!(center, Node::Float(_)) { coeff = number_node(if number.abs() < 1e-12 { 0.0 } else { number }) }
+        }
+        let term = mul(div(coeff, number_node(factorial), func_pos), pow(sub(Node::Variable(var.clone()), center.clone(), func_pos), Node::Int(k), func_pos), func_pos);
+        series = add(series, term, func_pos);
+    }
+    Ok(Value::Function(params, simplify_node(&series, func_pos)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simplified(text: &str) -> String {
+        let (node, pos) = parse(lex(&format!("{text}\n"), "<test>").unwrap(), "<test>").unwrap();
+        simplify_node(&node, &pos).to_string()
+    }
+
+    #[test]
+    fn symbolic_mode_keeps_undefined_names() {
+        assert!(matches!(eval_lines(&["x + 1"]), Err(Error::Variable(_, _, _))));
+        let value = eval_lines(&["symbolic(1)", "x^2 + 2*x"]).unwrap();
+        assert!(matches!(value, Value::Expr(_)));
+        assert_eq!(value.to_string(), "x^2 + 2 * x");
+        assert_eq!(eval_lines(&["symbolic(1)", "a : 3", "a * x"]).unwrap().to_string(), "3 * x");
+        assert!(matches!(eval_lines(&["symbolic(1)", "symbolic(0)", "y"]), Err(Error::Variable(_, _, _))));
+    }
+    #[test]
+    fn the_command_line_flag_sets_the_same_mode() {
+        let mut context = Context::seeded(0);
+        context.symbolic = true;
+        let (node, pos) = parse(lex("x * y\n", "<test>").unwrap(), "<test>").unwrap();
+        let value = interpret((&node, &pos), "<test>", &mut context).unwrap();
+        assert_eq!(value.to_string(), "x * y");
+        assert!(context.scope().symbolic);
+    }
+    #[test]
+    fn substitution_replaces_names() {
+        assert_eq!(eval_lines(&["symbolic(1)", "e : x^2 + 2*x", "e | x=3"]).unwrap().to_string(), "15");
+        assert_eq!(eval_lines(&["symbolic(1)", "e : x^2 + 2*x", "e | x=y+1"]).unwrap().to_string(), "2 * y + (y + 1)^2 + 2");
+        assert_eq!(eval_lines(&["symbolic(1)", "g : x^2 + 2*x + z", "g | [x=1 z=2]"]).unwrap().to_string(), "5");
+        assert_eq!(eval_lines(&["symbolic(1)", "e : x*y + 1", "e | z=1"]).unwrap().to_string(), "x * y + 1");
+        assert_eq!(eval_lines(&["f(x a) : a * x^2", "f | a=2"]).unwrap().to_string(), "function(x) : 2 * x^2");
+    }
+    #[test]
+    fn solve_is_exact_for_linear_and_quadratic_equations() {
+        assert_eq!(eval_lines(&["solve(2*x + 3 = 0 x)"]).unwrap().to_string(), "-3 / 2");
+        assert_eq!(eval_lines(&["solve(x^2 = 2 x)"]).unwrap().to_string(), "[-sqrt(2), sqrt(2)]");
+        assert_eq!(eval_lines(&["solve(x^2 - 2*x + 1 x)"]).unwrap().to_string(), "[1]");
+        assert_eq!(eval_lines(&["solve(x^2 + 1 = 0 x)"]).unwrap().to_string(), "[0 - 1i, 0 + 1i]");
+        assert_eq!(eval_lines(&["solve(x^2 + 2*x + 5 x)"]).unwrap().to_string(), "[-1 - 2i, -1 + 2i]");
+    }
+    #[test]
+    fn solve_eliminates_linear_systems() {
+        assert_eq!(eval_lines(&["solve([x + y = 3  x - y = 1] [x y])"]).unwrap().to_string(), "[2, 1]");
+        assert_eq!(eval_lines(&["a : 2", "solve([a*x + y = 5  y = 1] [x y])"]).unwrap().to_string(), "[2, 1]");
+        let singular = eval_lines(&["solve([x + y = 3  2*x + 2*y = 1] [x y])"]).unwrap_err();
+        assert!(singular.to_string().starts_with("ERROR: solve found no unique solution for the system"));
+        assert!(matches!(eval_lines(&["solve([x*y = 3  x = 1] [x y])"]), Err(Error::Domain(_, _, _))));
+    }
+    #[test]
+    fn collects_like_terms() {
+        assert_eq!(simplified("x + x"), "2 * x");
+        assert_eq!(simplified("x * y / x"), "y");
+        assert_eq!(simplified("x^2 * x^3 / x"), "x^4");
+        assert_eq!(simplified("(x^2)^3"), "x^6");
+    }
+    #[test]
+    fn keeps_the_domain_of_powers_and_quotients() {
+        assert_eq!(simplified("(x^0.5)^2"), "sqrt(x)^2");
+        assert_eq!(simplified("(x^2)^0.5"), "sqrt(x^2)");
+        assert_eq!(simplified("0 / 0 + x"), "x + 0 / 0");
+        assert_eq!(simplified("0 / x"), "0 / x");
+        assert_eq!(simplified("0 / 2 + x"), "x");
+    }
+    #[test]
+    fn int_finds_antiderivatives() {
+        assert_eq!(eval_lines(&["f(x) : 3*x^2 + 2*x", "int(f)"]).unwrap().to_string(), "function(x) : x^3 + x^2");
+        assert_eq!(eval_lines(&["q(x) : 1/x", "int(q)"]).unwrap().to_string(), "function(x) : ln(abs(x))");
+        for body in ["x*exp(x)", "x*sin(x)", "1/(x^2 + 1)", "x*exp(x^2)", "cos(2*x + 1)"] {
+            let define = format!("f(x) : {body}");
+            let check = eval_lines(&[&define, "g : d(int(f))", "[f(0.3) f(1.7)] - [g(0.3) g(1.7)]"]).unwrap();
+            let Value::Vector(differences) = check else { panic!("expected a vector") };
+            assert!(differences.iter().all(|x| x.number().unwrap().abs() < 1e-12), "{body}");
+        }
+        assert!(eval_lines(&["m(x) : exp(x^2)", "int(m)"]).unwrap_err().to_string().starts_with("ERROR: no closed form"));
+    }
+    #[test]
+    fn taylor_needs_smooth_derivatives() {
+        assert_eq!(eval_lines(&["taylor(sin 0 5)"]).unwrap().to_string(), "function(x) : x^5 / 120 - x^3 / 6 + x");
+        let check = eval_lines(&["t : taylor(exp 0.5 2)", "t(0.5) - exp(0.5)"]).unwrap();
+        assert!(check.number().unwrap().abs() < 1e-12);
+        for line in ["taylor(abs 0 2)", "taylor(sqrt 0 1)", "taylor(ln 0 1)"] {
+            assert!(eval_lines(&[line]).unwrap_err().to_string().starts_with("ERROR: taylor needs derivative"), "{line}");
+        }
+    }
+    #[test]
+    fn derivatives_resolve_names_like_calls() {
+        assert_eq!(eval_lines(&["f(x) : sin(x^2)", "d(f)"]).unwrap().to_string(), "function(x) : 2 * x * cos(x^2)");
+        assert_eq!(eval_lines(&["k(x y) : x^2 * y", "diff(k 0)"]).unwrap().to_string(), "function(x y) : 2 * x * y");
+        assert_eq!(eval_lines(&["k(x y) : x^2 * y", "diff(k y)"]).unwrap().to_string(), "function(x y) : x^2");
+        assert!(matches!(eval_lines(&["k(x y) : x^2 * y", "diff(k 2)"]), Err(Error::Index(2, 2, _, _))));
+        assert!(matches!(eval_lines(&["a : 3", "g(x) : a * x", "d(g)"]), Err(Error::Variable(_, _, _))));
+        assert!(matches!(eval_lines(&["h(x) : q(x)", "d(h)"]), Err(Error::Variable(_, _, _))));
+    }
+    #[test]
+    fn simplifies_expression_values() {
+        assert_eq!(eval_lines(&["symbolic(1)", "simplify((x - x) / 2 + y)"]).unwrap().to_string(), "y");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,363 @@
+use crate::position::*;
+use crate::error::*;
+use crate::lexer::*;
+use crate::interpreter::*;
+use crate::builtins::*;
+use crate::dual::*;
+
+// -- INDEXING -------------------------------------------------------------------------
+
+pub fn vector_index(len: usize, index: i64, pos: &Position, file_path: &str) -> Result<usize, Error> {
+    let idx = if index < 0 { index + len as i64 } else { index };
+    if idx < 0 || idx >= len as i64 {
+        return Err(Error::Index(index, len, pos.clone(), file_path.to_string()))
+    }
+    Ok(idx as usize)
+}
+pub fn slice(len: usize, start: &(Value, Position), end: &(Value, Position), step: Option<&(Value, Position)>,
+    pos: &Position, file_path: &str) -> Result<Value, Error> {
+    let bound = |(value, bound_pos): &(Value, Position)| match value {
+        Value::Int(index) if *index < 0 && *index + len as i64 >= 0 => Ok(*index + len as i64),
+        Value::Int(index) if *index >= 0 && *index <= len as i64 => Ok(*index),
+        Value::Int(index) => Err(Error::Index(*index, len, bound_pos.clone(), file_path.to_string())),
+        _ => Err(Error::IllegalValue(value.clone(), Type::Int, bound_pos.clone(), file_path.to_string()))
+    };
+    let (start, end) = (bound(start)?, bound(end)?);
+    let step = match step {
+        Some((Value::Int(step), _)) => *step,
+        Some((value, step_pos)) => return Err(Error::IllegalValue(value.clone(), Type::Int, step_pos.clone(), file_path.to_string())),
+        None => 1
+    };
+    if step == 0 { return Err(Error::Domain("range step cannot be zero".to_string(), pos.clone(), file_path.to_string())) }
+    let mut indices: Vec<Value> = vec![];
+    let mut i = start;
+    while (step > 0 && i < end) || (step < 0 && i > end) {
+        indices.push(Value::Int(i));
+        i += step;
+    }
+    Ok(Value::Vector(indices))
+}
+
+pub fn mask_indices(len: usize, indices: &[Value], pos: &Position, file_path: &str) -> Result<Vec<Value>, Error> {
+    if indices.is_empty() || !indices.iter().all(|index| matches!(index, Value::Bool(_))) {
+        return Ok(indices.to_vec())
+    }
+    if indices.len() != len {
+        return Err(Error::Shape(len, indices.len(), pos.clone(), file_path.to_string()))
+    }
+    Ok(indices.iter().enumerate()
+    .filter(|(_, mask)| mask == &&Value::Bool(true))
+    .map(|(i, _)| Value::Int(i as i64)).collect())
+}
+pub fn assign_index(target: &Value, index: &(Value, Position), value: &(Value, Position), file_path: &str) -> Result<Value, Error> {
+    let illegal = || Error::BinaryOperation(
+        Token::Hashtag, target.clone(), index.0.clone(), index.1.clone(), file_path.to_string()
+    );
+    let element = |v: &Value| match v {
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) => Ok(v.clone()),
+        Value::Vector(values) => Err(Error::Shape(1, values.len(), value.1.clone(), file_path.to_string())),
+        _ => Err(Error::IllegalValue(v.clone(), Type::Vector, value.1.clone(), file_path.to_string()))
+    };
+    let mut vector = match target {
+        Value::Vector(vector) => vector.clone(),
+        _ => return Err(illegal())
+    };
+    match &index.0 {
+        Value::Int(i) => {
+            let idx = vector_index(vector.len(), *i, &index.1, file_path)?;
+            vector[idx] = element(&value.0)?;
+        }
+        Value::Vector(indices) => {
+            let indices = mask_indices(vector.len(), indices, &index.1, file_path)?;
+            let values = match &value.0 {
+                Value::Vector(values) => {
+                    if values.len() != indices.len() {
+                        return Err(Error::Shape(indices.len(), values.len(), index.1.clone(), file_path.to_string()))
+                    }
+                    values.clone()
+                }
+                v => vec![v.clone(); indices.len()]
+            };
+            for (i, v) in indices.iter().zip(values.iter()) {
+                let Value::Int(i) = i else { return Err(illegal()) };
+                let idx = vector_index(vector.len(), *i, &index.1, file_path)?;
+                vector[idx] = element(v)?;
+            }
+        }
+        _ => return Err(illegal())
+    }
+    Ok(Value::Vector(vector))
+}
+
+// -- RANGES ---------------------------------------------------------------------------
+
+pub const MAX_RANGE: f64 = 1e8;
+
+fn range_number(arg: &(Value, Position), file_path: &str) -> Result<f64, Error> {
+    match &arg.0 {
+        Value::Int(int) => Ok(*int as f64),
+        Value::Float(float) => Ok(*float),
+        _ => Err(Error::IllegalValue(arg.0.clone(), Type::Vector, arg.1.clone(), file_path.to_string()))
+    }
+}
+pub fn range(start: &(Value, Position), end: &(Value, Position), step: Option<&(Value, Position)>,
+    pos: &Position, file_path: &str) -> Result<Value, Error> {
+    let zero_step = || Error::Domain("range step cannot be zero".to_string(), pos.clone(), file_path.to_string());
+    let too_long = |count: f64| Error::Domain(format!("range of {count} elements is too long"), pos.clone(), file_path.to_string());
+    let mut vector: Vec<Value> = vec![];
+    if let (Value::Int(start), Value::Int(end), None | Some((Value::Int(_), _))) = (&start.0, &end.0, step) {
+        let step = match step { Some((Value::Int(step), _)) => *step, _ => 1 };
+        if step == 0 { return Err(zero_step()) }
+        let count = (*end as i128 - *start as i128) / step as i128 + 1;
+        if count as f64 > MAX_RANGE { return Err(too_long(count as f64)) }
+        let mut i = *start;
+        while (step > 0 && i <= *end) || (step < 0 && i >= *end) {
+            vector.push(Value::Int(i));
+            i = match i.checked_add(step) { Some(i) => i, None => break };
+        }
+        return Ok(Value::Vector(vector))
+    }
+    let (start, end) = (range_number(start, file_path)?, range_number(end, file_path)?);
+    let step = match step { Some(step) => range_number(step, file_path)?, None => 1.0 };
+    if step == 0.0 { return Err(zero_step()) }
+    let count = ((end - start) / step + 1e-9).floor() + 1.0;
+    if !count.is_finite() {
+        return Err(Error::Domain("range must have finite bounds".to_string(), pos.clone(), file_path.to_string()))
+    }
+    if count > MAX_RANGE { return Err(too_long(count)) }
+    for i in 0..count.max(0.0) as usize {
+        vector.push(Value::Float(start + i as f64 * step));
+    }
+    Ok(Value::Vector(vector))
+}
+
+// -- BUILTINS -------------------------------------------------------------------------
+
+fn spaced(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str) -> Result<Vec<f64>, Error> {
+    expect_args(name, args, 3, pos, file_path)?;
+    let start = expect_float(name, &args[0], file_path)?;
+    let end = expect_float(name, &args[1], file_path)?;
+    let count = expect_int(name, &args[2], file_path)?;
+    if count < 1 { return Err(Error::Domain(
+        format!("{name} expects a positive count, got {count}"), args[2].1.clone(), file_path.to_string()
+    )) }
+    if count as f64 > MAX_RANGE { return Err(Error::Domain(
+        format!("range of {count} elements is too long"), args[2].1.clone(), file_path.to_string()
+    )) }
+    if count == 1 { return Ok(vec![start]) }
+    let step = (end - start) / (count - 1) as f64;
+    Ok((0..count).map(|i| if i == count - 1 { end } else { start + i as f64 * step }).collect())
+}
+pub fn linspace(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    let points = spaced("linspace", args, pos, file_path)?;
+    Ok(Value::Vector(points.into_iter().map(Value::Float).collect()))
+}
+pub fn logspace(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    let points = spaced("logspace", args, pos, file_path)?;
+    Ok(Value::Vector(points.into_iter().map(|x| Value::Float(10f64.powf(x))).collect()))
+}
+pub fn len(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("len", args, 1, pos, file_path)?;
+    Ok(Value::Int(expect_vector("len", &args[0], file_path)?.len() as i64))
+}
+pub fn push(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("push", args, 2, pos, file_path)?;
+    let mut vector = expect_vector("push", &args[0], file_path)?.clone();
+    match &args[1].0 {
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::Vector(_) => vector.push(args[1].0.clone()),
+        _ => return Err(illegal_arg("push", &args[1], file_path))
+    }
+    Ok(Value::Vector(vector))
+}
+pub fn reverse(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("reverse", args, 1, pos, file_path)?;
+    let mut vector = expect_vector("reverse", &args[0], file_path)?.clone();
+    vector.reverse();
+    Ok(Value::Vector(vector))
+}
+pub fn sort(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("sort", args, 1, pos, file_path)?;
+    let mut vector = expect_vector("sort", &args[0], file_path)?.clone();
+    if vector.iter().any(|v| real(v).is_none()) { return Err(illegal_arg("sort", &args[0], file_path)) }
+    vector.sort_by(|a, b| real(a).unwrap().total_cmp(&real(b).unwrap()));
+    Ok(Value::Vector(vector))
+}
+pub fn unique(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("unique", args, 1, pos, file_path)?;
+    let mut vector: Vec<Value> = vec![];
+    for value in expect_vector("unique", &args[0], file_path)? {
+        if !vector.iter().any(|v| equal_values(v, value)) { vector.push(value.clone()) }
+    }
+    Ok(Value::Vector(vector))
+}
+pub fn find(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("find", args, 2, pos, file_path)?;
+    let vector = expect_vector("find", &args[0], file_path)?;
+    let mut indices: Vec<Value> = vec![];
+    for (i, value) in vector.iter().enumerate() {
+        if equal_values(value, &args[1].0) { indices.push(Value::Int(i as i64)) }
+    }
+    Ok(Value::Vector(indices))
+}
+fn split_at(name: &str, args: &[(Value, Position)], pos: &Position, file_path: &str) -> Result<(Vec<Value>, usize, bool), Error> {
+    expect_args(name, args, 2, pos, file_path)?;
+    let vector = expect_vector(name, &args[0], file_path)?.clone();
+    let count = expect_int(name, &args[1], file_path)?;
+    let len = vector.len();
+    let at = if count < 0 { len.saturating_sub(count.unsigned_abs() as usize) } else { (count as usize).min(len) };
+    Ok((vector, at, count < 0))
+}
+pub fn take(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    let (vector, at, from_end) = split_at("take", args, pos, file_path)?;
+    Ok(Value::Vector(if from_end { vector[at..].to_vec() } else { vector[..at].to_vec() }))
+}
+pub fn drop(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    let (vector, at, from_end) = split_at("drop", args, pos, file_path)?;
+    Ok(Value::Vector(if from_end { vector[..at].to_vec() } else { vector[at..].to_vec() }))
+}
+pub fn zip(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("zip", args, 2, pos, file_path)?;
+    let v1 = expect_vector("zip", &args[0], file_path)?;
+    let v2 = expect_vector("zip", &args[1], file_path)?;
+    if v1.len() != v2.len() {
+        return Err(Error::Shape(v1.len(), v2.len(), args[1].1.clone(), file_path.to_string()))
+    }
+    Ok(Value::Vector(v1.iter().zip(v2.iter()).map(|(a, b)| Value::Vector(vec![a.clone(), b.clone()])).collect()))
+}
+fn flatten_into(vector: &[Value], values: &mut Vec<Value>) {
+    for value in vector {
+        match value {
+            Value::Vector(vector) => flatten_into(vector, values),
+            _ => values.push(value.clone())
+        }
+    }
+}
+pub fn flatten(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("flatten", args, 1, pos, file_path)?;
+    let mut values: Vec<Value> = vec![];
+    flatten_into(expect_vector("flatten", &args[0], file_path)?, &mut values);
+    Ok(Value::Vector(values))
+}
+pub fn repeat(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("repeat", args, 2, pos, file_path)?;
+    let count = expect_int("repeat", &args[1], file_path)?;
+    if count < 0 { return Err(Error::Domain(
+        format!("repeat expects a non-negative count, got {count}"), args[1].1.clone(), file_path.to_string()
+    )) }
+    let length = match &args[0].0 { Value::Vector(vector) => vector.len() as f64, _ => 1.0 };
+    if count as f64 * length > MAX_RANGE { return Err(Error::Domain(
+        format!("range of {} elements is too long", count as f64 * length), args[1].1.clone(), file_path.to_string()
+    )) }
+    match &args[0].0 {
+        Value::Vector(vector) => Ok(Value::Vector((0..count).flat_map(|_| vector.clone()).collect())),
+        Value::Int(_) | Value::Float(_) | Value::Bool(_) => Ok(Value::Vector(vec![args[0].0.clone(); count as usize])),
+        _ => Err(illegal_arg("repeat", &args[0], file_path))
+    }
+}
+fn truthy(name: &str, arg: &(Value, Position), file_path: &str) -> Result<Vec<bool>, Error> {
+    let vector = expect_vector(name, arg, file_path)?;
+    let mut values: Vec<bool> = vec![];
+    for value in vector {
+        match value.number() {
+            Some(number) => values.push(number != 0.0),
+            None => return Err(illegal_arg(name, arg, file_path))
+        }
+    }
+    Ok(values)
+}
+pub fn any(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("any", args, 1, pos, file_path)?;
+    Ok(Value::Bool(truthy("any", &args[0], file_path)?.into_iter().any(|b| b)))
+}
+pub fn all(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("all", args, 1, pos, file_path)?;
+    Ok(Value::Bool(truthy("all", &args[0], file_path)?.into_iter().all(|b| b)))
+}
+fn equal_values(v1: &Value, v2: &Value) -> bool {
+    match (v1, v2) {
+        (Value::Vector(v1), Value::Vector(v2)) =>
+        v1.len() == v2.len() && v1.iter().zip(v2.iter()).all(|(v1, v2)| equal_values(v1, v2)),
+        _ => match (v1.number(), v2.number()) {
+            (Some(n1), Some(n2)) => n1 == n2,
+            _ => v1 == v2
+        }
+    }
+}
+pub fn equal(args: &[(Value, Position)], pos: &Position, file_path: &str, _: &mut Context) -> Result<Value, Error> {
+    expect_args("equal", args, 2, pos, file_path)?;
+    Ok(Value::Bool(equal_values(&args[0].0, &args[1].0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shown(lines: &[&str]) -> String {
+        match eval_lines(lines) {
+            Ok(value) => value.to_string(),
+            Err(error) => error.to_string()
+        }
+    }
+
+    #[test]
+    fn ranges_take_a_step_and_a_length_cap() {
+        assert_eq!(shown(&["0..1 step 0.25"]), "[0, 0.25, 0.5, 0.75, 1]");
+        assert_eq!(shown(&["5..1 step (0-2)"]), "[5, 3, 1]");
+        assert_eq!(shown(&["step : 2", "1..3 step step"]), "[1, 3]");
+        assert!(shown(&["0..10^9"]).starts_with("ERROR: range of 1000000001 elements is too long"));
+        assert!(shown(&["0..1 step 10^(0-9)"]).starts_with("ERROR: range of 1000000000 elements is too long"));
+    }
+    #[test]
+    fn slices_stop_before_their_end() {
+        assert_eq!(shown(&["[1 2 3] # 1..3"]), "[2, 3]");
+        assert_eq!(shown(&["[1 2 3 4] # (0-2)..4"]), "[3, 4]");
+        assert_eq!(shown(&["[1 2 3 4 5] # 0..5 step 2"]), "[1, 3, 5]");
+        assert!(shown(&["[1 2 3] # 1..5"]).starts_with("ERROR: index 5 out of range for vector of length 3"));
+        assert!(shown(&["[1 2 3] # 0..2 step 0"]).starts_with("ERROR: range step cannot be zero"));
+        assert!(shown(&["[1 2 3] # 0..1.5"]).starts_with("ERROR: float illegal for int"));
+    }
+    #[test]
+    fn slice_assignment_uses_the_same_bounds() {
+        assert_eq!(shown(&["v : [10 2 3 4]", "v # 1..3 : [0 0]"]), "[10, 0, 0, 4]");
+        assert_eq!(shown(&["v : [1 2 3 4]", "v # (0-1) : 9"]), "[1, 2, 3, 9]");
+        assert_eq!(shown(&["v : [1 2 3 4]", "v # 0..4 step 2 : 0", "v"]), "[0, 2, 0, 4]");
+        assert!(shown(&["v : [1 2 3]", "v # 1..3 : [1 2 3]"]).starts_with("ERROR: shape mismatch"));
+        assert!(shown(&["v : [1 2 3]", "v # 0 : [7 8]"]).starts_with("ERROR: shape mismatch"));
+        assert!(shown(&["v : [1 2 3]", "v # [0 1] : [[7 8] [9 10]]"]).starts_with("ERROR: shape mismatch"));
+    }
+    #[test]
+    fn spaced_vectors_share_the_length_cap() {
+        assert_eq!(shown(&["linspace(0 1 3)"]), "[0, 0.5, 1]");
+        assert!(shown(&["linspace(0 1 100000000000)"]).starts_with("ERROR: range of 100000000000 elements is too long"));
+        assert!(shown(&["logspace(0 1 10^9)"]).starts_with("ERROR: range of 1000000000 elements is too long"));
+    }
+    #[test]
+    fn repeat_checks_the_result_length() {
+        assert_eq!(shown(&["repeat([1 2] 2)"]), "[1, 2, 1, 2]");
+        assert!(shown(&["repeat([1 2] 60000000)"]).starts_with("ERROR: range of 120000000 elements is too long"));
+    }
+    #[test]
+    fn comparisons_give_masks_that_filter() {
+        assert_eq!(shown(&["[1 5 3] > 2"]), "[false, true, true]");
+        assert_eq!(shown(&["[1 2 3] = [1 0 3]"]), "[true, false, true]");
+        assert!(shown(&["[1 2] < [1 2 3]"]).starts_with("ERROR: shape mismatch"));
+        assert_eq!(shown(&["v : [1 5 3 7]", "v # (v > 2)"]), "[5, 3, 7]");
+        assert_eq!(shown(&["v : [1 5 3 7]", "v # (v > 9)"]), "[]");
+        assert_eq!(shown(&["v : [1 5 3 7]", "v # (v > 2) : 0"]), "[1, 0, 0, 0]");
+        assert!(shown(&["[1 2 3] # ([1 2] > 0)"]).starts_with("ERROR: shape mismatch"));
+    }
+    #[test]
+    fn reducers_test_whole_vectors() {
+        assert_eq!(shown(&["any([1 5 3] > 4)"]), "true");
+        assert_eq!(shown(&["all([1 5 3] > 1)"]), "false");
+        assert_eq!(shown(&["all([])"]), "true");
+        assert_eq!(shown(&["any([])"]), "false");
+        assert_eq!(shown(&["equal([1 2] [1 2])"]), "true");
+        assert_eq!(shown(&["equal([1 2] [1 2 3])"]), "false");
+    }
+    #[test]
+    fn unique_and_find_compare_numbers_by_value() {
+        assert_eq!(shown(&["unique([1 1.0 2 2.5 2])"]), "[1, 2, 2.5]");
+        assert_eq!(shown(&["find([1 2.0 3 2] 2)"]), "[1, 3]");
+    }
+}
\ No newline at end of file